@@ -0,0 +1,168 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$                  /$$                 /$$$$$$$                                     /$$   /$$  /$$$$$$  /$$$$$$$$
+//  /$$__  $$                | $$                | $$__  $$                                   | $$  | $$ /$$__  $$|_____ $$/
+// | $$  \__/  /$$$$$$   /$$$$$$$  /$$$$$$       | $$  \ $$ /$$$$$$   /$$$$$$   /$$$$$$       | $$  | $$|__/  \ $$     /$$/ 
+// | $$       /$$__  $$ /$$__  $$ /$$__  $$      | $$$$$$$/|____  $$ /$$__  $$ /$$__  $$      | $$$$$$$$   /$$$$$/    /$$/  
+// | $$      | $$  \ $$| $$  | $$| $$$$$$$$      | $$____/  /$$$$$$$| $$  \ $$| $$$$$$$$      |_____  $$  |___  $$   /$$/   
+// | $$    $$| $$  | $$| $$  | $$| $$_____/      | $$      /$$__  $$| $$  | $$| $$_____/            | $$ /$$  \ $$  /$$/    
+// |  $$$$$$/|  $$$$$$/|  $$$$$$$|  $$$$$$$      | $$     |  $$$$$$$|  $$$$$$$|  $$$$$$$            | $$|  $$$$$$/ /$$/     
+//  \______/  \______/  \_______/ \_______/      |__/      \_______/ \____  $$ \_______/            |__/ \______/ |__/      
+//                                                                   /$$  \ $$                                              
+//                                                                  |  $$$$$$/                                              
+//                                                                   \______/                                               
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Translates Unicode characters onto the glyphs of code page 437, the character set built into
+//! the VGA text mode font.
+//!
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The glyph drawn in place of any character that code page 437 has no glyph for.
+pub const UNMAPPABLE: u8 = 0xfe;
+
+/// The characters drawn by the glyphs 0x00 through 0x1f. The glyph at 0x00 is blank, and so it is
+/// never mapped onto.
+const LOW_GLYPHS: [char; 32] = [
+    '\0',       '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►',        '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼'
+];
+
+/// The character drawn by the glyph 0x7f.
+const HOUSE_GLYPH: char = '⌂';
+
+/// The characters drawn by the glyphs 0x80 through 0xff.
+const HIGH_GLYPHS: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}'
+];
+
+/// Characters which have no glyph of their own, but which are near enough to an existing glyph to
+/// be drawn as it.
+const ALIASES: [(char, u8); 6] = [
+    ('\u{3b2}',  0xe1),    // Greek small beta, drawn with the sharp s.
+    ('\u{3bc}',  0xe6),    // Greek small mu, drawn with the micro sign.
+    ('\u{2211}', 0xe4),    // N-ary summation, drawn with the capital sigma.
+    ('\u{2126}', 0xea),    // Ohm sign, drawn with the capital omega.
+    ('\u{2205}', 0xed),    // Empty set, drawn with the small phi.
+    ('\u{2208}', 0xee)     // Element of, drawn with the small epsilon.
+];
+
+
+/*
+ * Code Page 437
+ *      Translation
+ */
+
+
+/// Maps a character onto the code page 437 glyph that draws it, if there is one. Printable ASCII
+/// maps onto itself, while ASCII control characters have no glyph.
+pub fn encode(char: char) -> Option<u8> {
+    match char {
+        ' '..='~'     => return Some(char as u8),
+        '\0'..='\x7f' => return None,
+        HOUSE_GLYPH   => return Some(0x7f),
+        _             => ()
+    }
+
+    if let Some(index) = LOW_GLYPHS.iter().skip(1).position(|glyph| *glyph == char) {
+        return Some(index as u8 + 0x01);
+    }
+    if let Some(index) = HIGH_GLYPHS.iter().position(|glyph| *glyph == char) {
+        return Some(index as u8 + 0x80);
+    }
+    ALIASES.iter().find(|(alias, _)| *alias == char).map(|(_, glyph)| *glyph)
+}
+
+/// Maps a character onto the code page 437 glyph that draws it, substituting [`UNMAPPABLE`] if
+/// there is none.
+pub fn encode_or_unmappable(char: char) -> u8 {
+    encode(char).unwrap_or(UNMAPPABLE)
+}
+
+/// Gets the character drawn by a code page 437 glyph. The blank glyph 0x00 yields a space.
+pub fn decode(glyph: u8) -> char {
+    match glyph {
+        0x00        => ' ',
+        0x01..=0x1f => LOW_GLYPHS[glyph as usize],
+        0x7f        => HOUSE_GLYPH,
+        0x80..=0xff => HIGH_GLYPHS[glyph as usize - 0x80],
+        _           => glyph as char
+    }
+}
+
+
+/*
+ * Code Page 437
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_ascii_maps_to_itself() -> () {
+    for byte in 0x20..=0x7e_u8 {
+        assert_eq!(encode(byte as char), Some(byte));
+    }
+    assert_eq!(encode('\n'), None);
+    assert_eq!(encode('\x7f'), None);
+}
+
+#[test_case]
+fn test_known_code_points() -> () {
+    assert_eq!(encode('─'), Some(0xc4));
+    assert_eq!(encode('│'), Some(0xb3));
+    assert_eq!(encode('┌'), Some(0xda));
+    assert_eq!(encode('╬'), Some(0xce));
+    assert_eq!(encode('█'), Some(0xdb));
+    assert_eq!(encode('░'), Some(0xb0));
+    assert_eq!(encode('α'), Some(0xe0));
+    assert_eq!(encode('π'), Some(0xe3));
+    assert_eq!(encode('\u{3a9}'), Some(0xea));
+    assert_eq!(encode('é'), Some(0x82));
+    assert_eq!(encode('Ñ'), Some(0xa5));
+    assert_eq!(encode('£'), Some(0x9c));
+    assert_eq!(encode('°'), Some(0xf8));
+    assert_eq!(encode('→'), Some(0x1a));
+    assert_eq!(encode('☺'), Some(0x01));
+    assert_eq!(encode('⌂'), Some(0x7f));
+    assert_eq!(encode('\u{a0}'), Some(0xff));
+}
+
+#[test_case]
+fn test_aliases() -> () {
+    assert_eq!(encode('\u{3b2}'), encode('\u{df}'));
+    assert_eq!(encode('\u{3bc}'), encode('\u{b5}'));
+    assert_eq!(encode('\u{2126}'), encode('\u{3a9}'));
+}
+
+#[test_case]
+fn test_unmappable() -> () {
+    assert_eq!(encode('あ'), None);
+    assert_eq!(encode('😀'), None);
+    assert_eq!(encode_or_unmappable('あ'), UNMAPPABLE);
+}
+
+#[test_case]
+fn test_round_trip() -> () {
+    for glyph in 0x01..=0xff_u8 {
+        assert_eq!(encode(decode(glyph)), Some(glyph));
+    }
+}
@@ -0,0 +1,612 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$   /$$$$$$       /$$ /$$$$$$        /$$   /$$                     /$$                                           /$$
+// | $$__  $$ /$$__  $$     /$$//$$__  $$      | $$  /$$/                    | $$                                          | $$
+// | $$  \ $$| $$  \__/    /$$/|__/  \ $$      | $$ /$$/   /$$$$$$  /$$   /$$| $$$$$$$   /$$$$$$   /$$$$$$   /$$$$$$   /$$$$$$$
+// | $$$$$$$/|  $$$$$$    /$$/   /$$$$$$/      | $$$$$/   /$$__  $$| $$  | $$| $$__  $$ /$$__  $$ |____  $$ /$$__  $$ /$$__  $$
+// | $$____/  \____  $$  /$$/   /$$____/       | $$  $$  | $$$$$$$$| $$  | $$| $$  \ $$| $$  \ $$  /$$$$$$$| $$  \__/| $$  | $$
+// | $$       /$$  \ $$ /$$/   | $$            | $$\  $$ | $$_____/| $$  | $$| $$  | $$| $$  | $$ /$$__  $$| $$      | $$  | $$
+// | $$      |  $$$$$$//$$/    | $$$$$$$$      | $$ \  $$|  $$$$$$$|  $$$$$$$| $$$$$$$/|  $$$$$$/|  $$$$$$$| $$      |  $$$$$$$
+// |__/       \______/|__/     |________/      |__/  \__/ \_______/ \____  $$|_______/  \______/  \_______/|__/       \_______/
+//                                                                  /$$  | $$                                                  
+//                                                                 |  $$$$$$/                                                  
+//                                                                  \______/                                                   
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A driver for the PS/2 keyboard, which decodes the scancodes received on IRQ 1 into key events
+//! and queues them for consumers to poll, block on or await.
+//!
+
+use core::pin::Pin;
+use core::future::Future;
+use core::task::{ Context, Poll, Waker };
+
+use x86_64::instructions::port::Port;
+use spin::Mutex;
+
+use crate::instructions::interrupts::{ self, InterruptIndex, IrqError };
+use crate::sync::ring_buffer::RingBuffer;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The PS/2 controller's data port, which scancodes are read from.
+const DATA_PORT: u16 = 0x60;
+
+/// The maximum amount of key events that may be queued before further events are dropped.
+pub const EVENT_QUEUE_SIZE: usize = 128;
+
+/// The decoder which scancodes received from the keyboard are fed into.
+static DECODER: Mutex<Decoder> = Mutex::new(Decoder::new(ScancodeSet::Set1));
+
+/// The queue of decoded key events, awaiting a consumer.
+static EVENTS: RingBuffer<KeyEvent, EVENT_QUEUE_SIZE> = RingBuffer::new();
+
+/// The waker of the task currently awaiting a key event, if any.
+static WAKER: Mutex<Option<Waker>> = Mutex::new(None);
+
+
+/*
+ * Key
+ *      Descriptors
+ */
+
+
+/// Encapsulates every physical key on a US 104-key keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+
+    // Function Row
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12, PrintScreen, ScrollLock,
+
+    // Number Row
+    Backtick, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, Minus, Equals, Backspace,
+
+    // Letter Rows
+    Tab, Q, W, E, R, T, Y, U, I, O, P, LeftBracket, RightBracket, Backslash,
+    CapsLock, A, S, D, F, G, H, J, K, L, Semicolon, Quote, Enter,
+    LeftShift, Z, X, C, V, B, N, M, Comma, Period, Slash, RightShift,
+    LeftControl, LeftGui, LeftAlt, Space, RightAlt, RightGui, Apps, RightControl,
+
+    // Navigation Cluster
+    Insert, Delete, Home, End, PageUp, PageDown, ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+
+    // Keypad
+    NumLock, KeypadSlash, KeypadAsterisk, KeypadMinus, KeypadPlus, KeypadEnter, KeypadPeriod,
+    Keypad0, Keypad1, Keypad2, Keypad3, Keypad4, Keypad5, Keypad6, Keypad7, Keypad8, Keypad9
+}
+
+/// Whether a key was pressed down or released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Pressed,
+    Released
+}
+
+/// The state of every modifier key at the time of a key event.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    pub left_shift:    bool,
+    pub right_shift:   bool,
+    pub left_control:  bool,
+    pub right_control: bool,
+    pub left_alt:      bool,
+    pub right_alt:     bool,
+    pub caps_lock:     bool,
+    pub num_lock:      bool
+}
+
+impl Modifiers {
+
+    /// Creates a modifier state with no modifier held or locked.
+    pub const fn new() -> Self {
+        Modifiers {
+            left_shift:    false,
+            right_shift:   false,
+            left_control:  false,
+            right_control: false,
+            left_alt:      false,
+            right_alt:     false,
+            caps_lock:     false,
+            num_lock:      false
+        }
+    }
+
+    /// Whether either shift key is held.
+    pub fn shift(&self) -> bool {
+        self.left_shift || self.right_shift
+    }
+
+    /// Whether either control key is held.
+    pub fn control(&self) -> bool {
+        self.left_control || self.right_control
+    }
+
+    /// Whether either alt key is held.
+    pub fn alt(&self) -> bool {
+        self.left_alt || self.right_alt
+    }
+
+    /// Updates the modifier state from a key event, returning whether the key was a modifier.
+    /// # Note
+    /// Lock keys only toggle when first pressed, as holding a key repeats its press.
+    fn update(&mut self, code: KeyCode, state: KeyState, repeat: bool) -> bool {
+        let pressed: bool = state == KeyState::Pressed;
+        match code {
+            KeyCode::LeftShift    => self.left_shift    = pressed,
+            KeyCode::RightShift   => self.right_shift   = pressed,
+            KeyCode::LeftControl  => self.left_control  = pressed,
+            KeyCode::RightControl => self.right_control = pressed,
+            KeyCode::LeftAlt      => self.left_alt      = pressed,
+            KeyCode::RightAlt     => self.right_alt     = pressed,
+            KeyCode::CapsLock     => self.caps_lock    ^= pressed && !repeat,
+            KeyCode::NumLock      => self.num_lock     ^= pressed && !repeat,
+            _                     => return false
+        }
+        true
+    }
+}
+
+/// A single decoded key press or release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+
+    /// The physical key which changed state.
+    pub code: KeyCode,
+
+    /// Whether the key was pressed or released.
+    pub state: KeyState,
+
+    /// The modifier state after the event was applied.
+    pub modifiers: Modifiers,
+
+    /// The character the key press produces under the US layout, if any.
+    /// # Note
+    /// Releases never produce a character.
+    pub character: Option<char>
+}
+
+
+/*
+ * Scancode
+ *      Decoder
+ */
+
+
+/// The scancode sets a PS/2 keyboard may send.
+/// # Note
+/// Most PS/2 controllers translate set 2 into set 1 by default, so set 1 is what is usually
+/// received on IRQ 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScancodeSet {
+    Set1,
+    Set2
+}
+
+/// A state machine that decodes a stream of raw scancodes into key events, tracking multi-byte
+/// sequences and the modifier state as it goes.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    set:       ScancodeSet,
+    extended:  bool,     // Whether the previous byte was an 0xE0 prefix.
+    release:   bool,     // Whether the previous byte was a scancode set 2 0xF0 release prefix.
+    skip:      u8,       // The amount of bytes left to discard from a pause key sequence.
+    held:      u128,     // A bit for every key currently held down, indexed by its key code.
+    modifiers: Modifiers
+}
+
+impl Decoder {
+
+    /// Creates a new decoder for the given scancode set.
+    pub const fn new(set: ScancodeSet) -> Self {
+        Decoder {
+            set,
+            extended:  false,
+            release:   false,
+            skip:      0,
+            held:      0,
+            modifiers: Modifiers::new()
+        }
+    }
+
+    /// Gets the scancode set being decoded.
+    pub fn set(&self) -> ScancodeSet {
+        self.set
+    }
+
+    /// Gets the current modifier state.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Feeds a single scancode byte into the decoder, returning a key event if the byte completed a
+    /// sequence describing a known key.
+    pub fn feed(&mut self, byte: u8) -> Option<KeyEvent> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+
+        let (code, state): (u8, KeyState) = match (self.set, byte) {
+            (_, 0xE0)                 => { self.extended = true; return None; },
+            (ScancodeSet::Set1, 0xE1) => { self.skip = 5;        return None; },
+            (ScancodeSet::Set2, 0xE1) => { self.skip = 7;        return None; },
+            (ScancodeSet::Set2, 0xF0) => { self.release = true;  return None; },
+
+
+            (ScancodeSet::Set1, _) if byte & 0x80 != 0 => (byte & 0x7F, KeyState::Released),
+            (ScancodeSet::Set1, _)                     => (byte,        KeyState::Pressed),
+            (ScancodeSet::Set2, _) if self.release     => (byte,        KeyState::Released),
+            (ScancodeSet::Set2, _)                     => (byte,        KeyState::Pressed)
+        };
+
+        let extended: bool = self.extended;
+        self.extended = false;
+        self.release  = false;
+
+        let code: KeyCode = match (self.set, extended) {
+            (ScancodeSet::Set1, false) => set1_key(code),
+            (ScancodeSet::Set1, true)  => set1_extended_key(code),
+            (ScancodeSet::Set2, false) => set2_key(code),
+            (ScancodeSet::Set2, true)  => set2_extended_key(code)
+        }?;
+
+        let bit:    u128 = 1 << code as u32;
+        let repeat: bool = state == KeyState::Pressed && self.held & bit != 0;
+        match state {
+            KeyState::Pressed  => self.held |= bit,
+            KeyState::Released => self.held &= !bit
+        }
+
+        let is_modifier: bool         = self.modifiers.update(code, state, repeat);
+        let character:   Option<char> = match state {
+            KeyState::Pressed if !is_modifier => us_layout(code, &self.modifiers),
+            _                                 => None
+        };
+        
+        Some(KeyEvent {
+            code,
+            state,
+            modifiers: self.modifiers,
+            character
+        })
+    }
+}
+
+/// Maps a scancode set 1 make code onto its key.
+fn set1_key(code: u8) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        0x01 => Escape,          0x02 => Key1,            0x03 => Key2,            0x04 => Key3,            0x05 => Key4,
+        0x06 => Key5,            0x07 => Key6,            0x08 => Key7,            0x09 => Key8,            0x0A => Key9,
+        0x0B => Key0,            0x0C => Minus,           0x0D => Equals,          0x0E => Backspace,       0x0F => Tab,
+        0x10 => Q,               0x11 => W,               0x12 => E,               0x13 => R,               0x14 => T,
+        0x15 => Y,               0x16 => U,               0x17 => I,               0x18 => O,               0x19 => P,
+        0x1A => LeftBracket,     0x1B => RightBracket,    0x1C => Enter,           0x1D => LeftControl,     0x1E => A,
+        0x1F => S,               0x20 => D,               0x21 => F,               0x22 => G,               0x23 => H,
+        0x24 => J,               0x25 => K,               0x26 => L,               0x27 => Semicolon,       0x28 => Quote,
+        0x29 => Backtick,        0x2A => LeftShift,       0x2B => Backslash,       0x2C => Z,               0x2D => X,
+        0x2E => C,               0x2F => V,               0x30 => B,               0x31 => N,               0x32 => M,
+        0x33 => Comma,           0x34 => Period,          0x35 => Slash,           0x36 => RightShift,      0x37 => KeypadAsterisk,
+        0x38 => LeftAlt,         0x39 => Space,           0x3A => CapsLock,        0x3B => F1,              0x3C => F2,
+        0x3D => F3,              0x3E => F4,              0x3F => F5,              0x40 => F6,              0x41 => F7,
+        0x42 => F8,              0x43 => F9,              0x44 => F10,             0x45 => NumLock,         0x46 => ScrollLock,
+        0x47 => Keypad7,         0x48 => Keypad8,         0x49 => Keypad9,         0x4A => KeypadMinus,     0x4B => Keypad4,
+        0x4C => Keypad5,         0x4D => Keypad6,         0x4E => KeypadPlus,      0x4F => Keypad1,         0x50 => Keypad2,
+        0x51 => Keypad3,         0x52 => Keypad0,         0x53 => KeypadPeriod,    0x57 => F11,             0x58 => F12,
+        _    => return None
+    })
+}
+
+/// Maps a scancode set 1 make code following an 0xE0 prefix onto its key.
+/// # Note
+/// The fake shifts that some keyboards wrap around the navigation cluster and print screen key are
+/// not mapped, so that they are discarded.
+fn set1_extended_key(code: u8) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        0x1C => KeypadEnter,   0x1D => RightControl,  0x35 => KeypadSlash,   0x37 => PrintScreen,   0x38 => RightAlt,
+        0x47 => Home,          0x48 => ArrowUp,       0x49 => PageUp,        0x4B => ArrowLeft,     0x4D => ArrowRight,
+        0x4F => End,           0x50 => ArrowDown,     0x51 => PageDown,      0x52 => Insert,        0x53 => Delete,
+        0x5B => LeftGui,       0x5C => RightGui,      0x5D => Apps,
+        _    => return None
+    })
+}
+
+/// Maps a scancode set 2 make code onto its key.
+fn set2_key(code: u8) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        0x01 => F9,              0x03 => F5,              0x04 => F3,              0x05 => F1,              0x06 => F2,
+        0x07 => F12,             0x09 => F10,             0x0A => F8,              0x0B => F6,              0x0C => F4,
+        0x0D => Tab,             0x0E => Backtick,        0x11 => LeftAlt,         0x12 => LeftShift,       0x14 => LeftControl,
+        0x15 => Q,               0x16 => Key1,            0x1A => Z,               0x1B => S,               0x1C => A,
+        0x1D => W,               0x1E => Key2,            0x21 => C,               0x22 => X,               0x23 => D,
+        0x24 => E,               0x25 => Key4,            0x26 => Key3,            0x29 => Space,           0x2A => V,
+        0x2B => F,               0x2C => T,               0x2D => R,               0x2E => Key5,            0x31 => N,
+        0x32 => B,               0x33 => H,               0x34 => G,               0x35 => Y,               0x36 => Key6,
+        0x3A => M,               0x3B => J,               0x3C => U,               0x3D => Key7,            0x3E => Key8,
+        0x41 => Comma,           0x42 => K,               0x43 => I,               0x44 => O,               0x45 => Key0,
+        0x46 => Key9,            0x49 => Period,          0x4A => Slash,           0x4B => L,               0x4C => Semicolon,
+        0x4D => P,               0x4E => Minus,           0x52 => Quote,           0x54 => LeftBracket,     0x55 => Equals,
+        0x58 => CapsLock,        0x59 => RightShift,      0x5A => Enter,           0x5B => RightBracket,    0x5D => Backslash,
+        0x66 => Backspace,       0x69 => Keypad1,         0x6B => Keypad4,         0x6C => Keypad7,         0x70 => Keypad0,
+        0x71 => KeypadPeriod,    0x72 => Keypad2,         0x73 => Keypad5,         0x74 => Keypad6,         0x75 => Keypad8,
+        0x76 => Escape,          0x77 => NumLock,         0x78 => F11,             0x79 => KeypadPlus,      0x7A => Keypad3,
+        0x7B => KeypadMinus,     0x7C => KeypadAsterisk,  0x7D => Keypad9,         0x7E => ScrollLock,      0x83 => F7,
+        _    => return None
+    })
+}
+
+/// Maps a scancode set 2 make code following an 0xE0 prefix onto its key.
+/// # Note
+/// The fake shifts that some keyboards wrap around the navigation cluster and print screen key are
+/// not mapped, so that they are discarded.
+fn set2_extended_key(code: u8) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        0x11 => RightAlt,      0x14 => RightControl,  0x1F => LeftGui,       0x27 => RightGui,      0x2F => Apps,
+        0x4A => KeypadSlash,   0x5A => KeypadEnter,   0x69 => End,           0x6B => ArrowLeft,     0x6C => Home,
+        0x70 => Insert,        0x71 => Delete,        0x72 => ArrowDown,     0x74 => ArrowRight,    0x75 => ArrowUp,
+        0x7A => PageDown,      0x7C => PrintScreen,   0x7D => PageUp,
+        _    => return None
+    })
+}
+
+/// Maps a key onto the character it produces under the US layout, given the current modifiers.
+fn us_layout(code: KeyCode, modifiers: &Modifiers) -> Option<char> {
+    use KeyCode::*;
+
+    /// Picks the unshifted or shifted variant of a character.
+    fn shifted(shift: bool, lower: char, upper: char) -> Option<char> {
+        Some(if shift { upper } else { lower })
+    }
+
+    let shift:   bool = modifiers.shift();
+    let letters: bool = shift ^ modifiers.caps_lock;
+    let keypad:  bool = modifiers.num_lock;
+    match code {
+        A => shifted(letters, 'a', 'A'), B => shifted(letters, 'b', 'B'), C => shifted(letters, 'c', 'C'),
+        D => shifted(letters, 'd', 'D'), E => shifted(letters, 'e', 'E'), F => shifted(letters, 'f', 'F'),
+        G => shifted(letters, 'g', 'G'), H => shifted(letters, 'h', 'H'), I => shifted(letters, 'i', 'I'),
+        J => shifted(letters, 'j', 'J'), K => shifted(letters, 'k', 'K'), L => shifted(letters, 'l', 'L'),
+        M => shifted(letters, 'm', 'M'), N => shifted(letters, 'n', 'N'), O => shifted(letters, 'o', 'O'),
+        P => shifted(letters, 'p', 'P'), Q => shifted(letters, 'q', 'Q'), R => shifted(letters, 'r', 'R'),
+        S => shifted(letters, 's', 'S'), T => shifted(letters, 't', 'T'), U => shifted(letters, 'u', 'U'),
+        V => shifted(letters, 'v', 'V'), W => shifted(letters, 'w', 'W'), X => shifted(letters, 'x', 'X'),
+        Y => shifted(letters, 'y', 'Y'), Z => shifted(letters, 'z', 'Z'),
+
+        Key1 => shifted(shift, '1', '!'), Key2 => shifted(shift, '2', '@'), Key3 => shifted(shift, '3', '#'),
+        Key4 => shifted(shift, '4', '$'), Key5 => shifted(shift, '5', '%'), Key6 => shifted(shift, '6', '^'),
+        Key7 => shifted(shift, '7', '&'), Key8 => shifted(shift, '8', '*'), Key9 => shifted(shift, '9', '('),
+        Key0 => shifted(shift, '0', ')'),
+
+        Backtick     => shifted(shift, '`',  '~'), Minus     => shifted(shift, '-',  '_'), Equals => shifted(shift, '=', '+'),
+        LeftBracket  => shifted(shift, '[',  '{'), Semicolon => shifted(shift, ';',  ':'), Comma  => shifted(shift, ',', '<'),
+        RightBracket => shifted(shift, ']',  '}'), Quote     => shifted(shift, '\'', '"'), Period => shifted(shift, '.', '>'),
+        Backslash    => shifted(shift, '\\', '|'), Slash     => shifted(shift, '/',  '?'),
+
+        Space     => Some(' '),
+        Tab       => Some('\t'),
+        Enter     => Some('\n'),
+        Backspace => Some('\x08'),
+        Escape    => Some('\x1b'),
+        Delete    => Some('\x7f'),
+
+        KeypadSlash    => Some('/'),
+        KeypadAsterisk => Some('*'),
+        KeypadMinus    => Some('-'),
+        KeypadPlus     => Some('+'),
+        KeypadEnter    => Some('\n'),
+        KeypadPeriod if keypad => Some('.'),
+        Keypad0 if keypad => Some('0'), Keypad1 if keypad => Some('1'), Keypad2 if keypad => Some('2'),
+        Keypad3 if keypad => Some('3'), Keypad4 if keypad => Some('4'), Keypad5 if keypad => Some('5'),
+        Keypad6 if keypad => Some('6'), Keypad7 if keypad => Some('7'), Keypad8 if keypad => Some('8'),
+        Keypad9 if keypad => Some('9'),
+        
+        _ => None
+    }
+}
+
+
+/*
+ * Initialization
+ *      Routines
+ */
+
+
+/// Registers the keyboard's handler on IRQ 1, decoding scancodes from the given scancode set.
+pub fn init(set: ScancodeSet) -> Result<(), IrqError> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        *DECODER.lock() = Decoder::new(set);
+    });
+
+    match interrupts::register_irq(InterruptIndex::Keyboard, keyboard_handler) {
+        Ok(()) | Err(IrqError::AlreadyRegistered) => Ok(()),
+        Err(error)                                => Err(error)
+    }
+}
+
+/// Handles a single keyboard interrupt by decoding the received scancode and queueing the event.
+/// # Note
+/// Events are silently dropped if the queue is full, as there is nobody to report the loss to from
+/// within an interrupt.
+fn keyboard_handler(_: InterruptIndex) -> () {
+    let mut port: Port<u8> = Port::new(DATA_PORT);
+    let scancode: u8       = unsafe { port.read() };
+
+    if let Some(event) = DECODER.lock().feed(scancode) {
+        let _ = EVENTS.push(event);
+        if let Some(waker) = WAKER.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+
+/*
+ * Event
+ *      Consumption
+ */
+
+
+/// Pops the oldest queued key event, if there is one.
+pub fn try_read_event() -> Option<KeyEvent> {
+    EVENTS.pop()
+}
+
+/// Halts the CPU until a key event arrives, and pops it.
+/// # Panics
+/// Panics if interrupts are disabled, as no key event could ever arrive.
+pub fn read_event() -> KeyEvent {
+    assert!(x86_64::instructions::interrupts::are_enabled(), "Cannot wait for a key whilst interrupts are disabled");
+    loop {
+        if let Some(event) = try_read_event() {
+            return event;
+        }
+        x86_64::instructions::hlt();
+    }
+}
+
+/// Creates a future that resolves to the next key event.
+pub fn next_event() -> NextKeyEvent {
+    NextKeyEvent
+}
+
+/// A future that resolves to the next queued key event.
+/// # Note
+/// Only a single task may await a key event at any given time, as only the most recent waker is
+/// woken.
+#[derive(Debug)]
+pub struct NextKeyEvent;
+
+impl Future for NextKeyEvent {
+    type Output = KeyEvent;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<KeyEvent> {
+        if let Some(event) = try_read_event() {
+            return Poll::Ready(event);
+        }
+
+        // Register before checking again, so that an event queued in between is not missed.
+        x86_64::instructions::interrupts::without_interrupts(|| {
+            *WAKER.lock() = Some(cx.waker().clone());
+        });
+        match try_read_event() {
+            Some(event) => {
+                x86_64::instructions::interrupts::without_interrupts(|| WAKER.lock().take());
+                Poll::Ready(event)
+            },
+            None => Poll::Pending
+        }
+    }
+}
+
+
+/*
+ * Keyboard Driver
+ *      Tests
+ */
+
+
+#[cfg(test)]
+fn feed_all(decoder: &mut Decoder, scancodes: &[u8]) -> Option<KeyEvent> {
+    scancodes.iter().fold(None, |_, scancode| decoder.feed(*scancode))
+}
+
+#[test_case]
+fn test_set1_press_release() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set1);
+    
+    let event: KeyEvent = decoder.feed(0x1E).unwrap();
+    assert_eq!(event.code,      KeyCode::A);
+    assert_eq!(event.state,     KeyState::Pressed);
+    assert_eq!(event.character, Some('a'));
+
+    let event: KeyEvent = decoder.feed(0x9E).unwrap();
+    assert_eq!(event.code,      KeyCode::A);
+    assert_eq!(event.state,     KeyState::Released);
+    assert_eq!(event.character, None);
+}
+
+#[test_case]
+fn test_set1_modifiers() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set1);
+    
+    assert_eq!(feed_all(&mut decoder, &[0x2A, 0x1E]).unwrap().character, Some('A'));
+    assert_eq!(feed_all(&mut decoder, &[0x02]).unwrap().character,       Some('!'));
+    assert_eq!(feed_all(&mut decoder, &[0xAA, 0x02]).unwrap().character, Some('1'));
+
+    // Caps lock only affects letters, and is inverted by shift.
+    assert_eq!(feed_all(&mut decoder, &[0x3A, 0xBA, 0x1E]).unwrap().character, Some('A'));
+    assert_eq!(feed_all(&mut decoder, &[0x0C]).unwrap().character,             Some('-'));
+    assert_eq!(feed_all(&mut decoder, &[0x36, 0x1E]).unwrap().character,       Some('a'));
+    assert!(decoder.modifiers().caps_lock);
+}
+
+#[test_case]
+fn test_lock_keys_ignore_repeats() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set1);
+
+    // Holding caps lock repeats its press, which must only toggle it once.
+    feed_all(&mut decoder, &[0x3A, 0x3A, 0x3A, 0xBA]);
+    assert!(decoder.modifiers().caps_lock);
+    feed_all(&mut decoder, &[0x3A, 0x3A, 0xBA]);
+    assert!(!decoder.modifiers().caps_lock);
+
+    feed_all(&mut decoder, &[0x45, 0x45, 0xC5]);
+    assert!(decoder.modifiers().num_lock);
+}
+
+#[test_case]
+fn test_set1_extended_keys() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set1);
+
+    assert_eq!(feed_all(&mut decoder, &[0xE0, 0x48]).unwrap().code,  KeyCode::ArrowUp);
+    assert_eq!(feed_all(&mut decoder, &[0xE0, 0xC8]).unwrap().state, KeyState::Released);
+    assert_eq!(feed_all(&mut decoder, &[0x48]).unwrap().code,        KeyCode::Keypad8);
+    
+    // Fake shifts around the navigation cluster are discarded.
+    assert_eq!(feed_all(&mut decoder, &[0xE0, 0x2A]), None);
+    assert!(!decoder.modifiers().shift());
+    
+    let event: KeyEvent = feed_all(&mut decoder, &[0xE0, 0x1D]).unwrap();
+    assert_eq!(event.code, KeyCode::RightControl);
+    assert!(event.modifiers.control());
+}
+
+#[test_case]
+fn test_set2_decoding() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set2);
+
+    assert_eq!(feed_all(&mut decoder, &[0x1C]).unwrap().character, Some('a'));
+    
+    let event: KeyEvent = feed_all(&mut decoder, &[0xF0, 0x1C]).unwrap();
+    assert_eq!(event.code,  KeyCode::A);
+    assert_eq!(event.state, KeyState::Released);
+
+    assert_eq!(feed_all(&mut decoder, &[0x12, 0x16]).unwrap().character, Some('!'));
+    assert_eq!(feed_all(&mut decoder, &[0xE0, 0xF0, 0x75]).unwrap(), KeyEvent {
+        code:      KeyCode::ArrowUp,
+        state:     KeyState::Released,
+        modifiers: decoder.modifiers(),
+        character: None
+    });
+}
+
+#[test_case]
+fn test_pause_sequence_discarded() -> () {
+    let mut decoder: Decoder = Decoder::new(ScancodeSet::Set1);
+    assert_eq!(feed_all(&mut decoder, &[0xE1, 0x1D, 0x45, 0xE1, 0x9D, 0xC5]), None);
+    assert_eq!(decoder.feed(0x1E).unwrap().character, Some('a'));
+}
@@ -1,2 +1,5 @@
 pub mod vga_text;
+pub mod cp437;
 pub mod serial;
+pub mod pit;
+pub mod keyboard;
@@ -0,0 +1,190 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$             /$$                                             /$$       /$$$$$$$$ /$$                                  
+// |_  $$_/            | $$                                            | $$      |__  $$__/|__/                                  
+//   | $$   /$$$$$$$  /$$$$$$    /$$$$$$   /$$$$$$  /$$    /$$ /$$$$$$ | $$         | $$    /$$ /$$$$$$/$$$$   /$$$$$$   /$$$$$$ 
+//   | $$  | $$__  $$|_  $$_/   /$$__  $$ /$$__  $$|  $$  /$$/|____  $$| $$         | $$   | $$| $$_  $$_  $$ /$$__  $$ /$$__  $$
+//   | $$  | $$  \ $$  | $$    | $$$$$$$$| $$  \__/ \  $$/$$/  /$$$$$$$| $$         | $$   | $$| $$ \ $$ \ $$| $$$$$$$$| $$  \__/
+//   | $$  | $$  | $$  | $$ /$$| $$_____/| $$        \  $$$/  /$$__  $$| $$         | $$   | $$| $$ | $$ | $$| $$_____/| $$      
+//  /$$$$$$| $$  | $$  |  $$$$/|  $$$$$$$| $$         \  $/  |  $$$$$$$| $$         | $$   | $$| $$ | $$ | $$|  $$$$$$$| $$      
+// |______/|__/  |__/   \___/   \_______/|__/          \_/    \_______/|__/         |__/   |__/|__/ |__/ |__/ \_______/|__/      
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A driver for the Intel 8253/8254 Programmable Interval Timer (PIT), which keeps a global tick
+//! counter from its IRQ 0 interrupts and provides the kernel's notion of time.
+//!
+
+use core::time::Duration;
+use core::sync::atomic::{ AtomicU32, AtomicU64, Ordering };
+
+use x86_64::instructions::port::Port;
+use spin::Mutex;
+
+use crate::instructions::interrupts::{ self, InterruptIndex, IrqError };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The frequency of the PIT's input clock, in hertz.
+pub const BASE_FREQUENCY: u32 = 1_193_182;
+
+/// The frequency the PIT is programmed to by default, in hertz, giving one tick per millisecond.
+pub const DEFAULT_FREQUENCY: u32 = 1000;
+
+/// The data port of the PIT's first channel, along with the PIT's mode/command port.
+const CHANNEL_0_PORT: u16 = 0x40;
+const COMMAND_PORT:   u16 = 0x43;
+
+/// Selects channel 0, lobyte/hibyte access, mode 3 (square wave generator) and binary counting.
+const CHANNEL_0_SQUARE_WAVE: u8 = 0b0011_0110;
+
+/// The amount of timer interrupts received since the PIT was initialized.
+static TICKS: AtomicU64 = AtomicU64::new(0);
+
+/// The frequency the PIT is currently programmed to, in hertz. A value of zero marks that the PIT
+/// has not been initialized.
+static FREQUENCY: AtomicU32 = AtomicU32::new(0);
+
+/// The PIT's channel 0 data port and command port.
+static PORTS: Mutex<(Port<u8>, Port<u8>)> = Mutex::new((Port::new(CHANNEL_0_PORT), Port::new(COMMAND_PORT)));
+
+
+/*
+ * Initialization
+ *      Routines
+ */
+
+
+/// Programs the PIT's first channel to fire at the given frequency, and registers the tick handler
+/// on IRQ 0.
+/// # Note
+/// The frequency is clamped to the range the PIT supports (roughly 19 Hz to 1.19 MHz), and as the
+/// PIT can only divide its base frequency by an integer, the frequency that is actually programmed
+/// may differ slightly. It can be read back via `frequency()`.
+pub fn init(frequency: u32) -> Result<(), IrqError> {
+    let divisor: u32 = (BASE_FREQUENCY / frequency.max(1)).clamp(1, u16::MAX as u32);
+    
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let mut ports: spin::MutexGuard<(Port<u8>, Port<u8>)> = PORTS.lock();
+        unsafe {
+            ports.1.write(CHANNEL_0_SQUARE_WAVE);
+            ports.0.write(divisor as u8);
+            ports.0.write((divisor >> 8) as u8);
+        }
+        FREQUENCY.store(BASE_FREQUENCY / divisor, Ordering::Release);
+    });
+
+    match interrupts::register_irq(InterruptIndex::Timer, tick_handler) {
+        Ok(()) | Err(IrqError::AlreadyRegistered) => Ok(()),
+        Err(error)                                => Err(error)
+    }
+}
+
+/// Handles a single timer interrupt.
+fn tick_handler(_: InterruptIndex) -> () {
+    TICKS.fetch_add(1, Ordering::Relaxed);
+}
+
+
+/*
+ * Time
+ *      Queries
+ */
+
+
+/// Gets the frequency the PIT is currently programmed to, in hertz, or zero if the PIT has not been
+/// initialized.
+pub fn frequency() -> u32 {
+    FREQUENCY.load(Ordering::Acquire)
+}
+
+/// Gets the amount of ticks that have elapsed since the PIT was initialized.
+pub fn ticks() -> u64 {
+    TICKS.load(Ordering::Relaxed)
+}
+
+/// Gets the amount of time that has elapsed since the PIT was initialized.
+pub fn uptime() -> Duration {
+    match frequency() {
+        0         => Duration::ZERO,
+        frequency => {
+            let ticks: u64 = ticks();
+            let secs:  u64 = ticks / frequency as u64;
+            let nanos: u64 = (ticks % frequency as u64) * 1_000_000_000 / frequency as u64;
+            Duration::new(secs, nanos as u32)
+        }
+    }
+}
+
+/// Converts a duration in milliseconds into the amount of ticks it spans, rounding up.
+pub fn ms_to_ticks(ms: u64) -> u64 {
+    ms.saturating_mul(frequency() as u64).div_ceil(1000)
+}
+
+
+/*
+ * Sleep
+ *      Routines
+ */
+
+
+/// Halts the CPU until at least the given amount of ticks have elapsed.
+/// # Panics
+/// Panics if interrupts are disabled, as no tick could ever arrive to end the sleep.
+pub fn sleep_ticks(ticks: u64) -> () {
+    assert!(x86_64::instructions::interrupts::are_enabled(), "Cannot sleep whilst interrupts are disabled");
+    
+    let target: u64 = self::ticks() + ticks;
+    while self::ticks() < target {
+        x86_64::instructions::hlt();
+    }
+}
+
+/// Halts the CPU until at least the given amount of milliseconds have elapsed.
+/// # Panics
+/// Panics if interrupts are disabled, as no tick could ever arrive to end the sleep.
+pub fn sleep_ms(ms: u64) -> () {
+    sleep_ticks(ms_to_ticks(ms));
+}
+
+
+/*
+ * PIT Driver
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_ticks_advance() -> () {
+    let start: u64 = ticks();
+    for _ in 0..10_000_000 {
+        core::hint::spin_loop();
+        if ticks() > start {
+            return;
+        }
+    }
+    panic!("No timer ticks arrived during the busy wait");
+}
+
+#[test_case]
+fn test_sleep_ms() -> () {
+    let start: Duration = uptime();
+    sleep_ms(20);
+    assert!(uptime() - start >= Duration::from_millis(20));
+}
+
+#[test_case]
+fn test_programmed_frequency() -> () {
+    assert_eq!(frequency(), BASE_FREQUENCY / (BASE_FREQUENCY / DEFAULT_FREQUENCY));
+    assert_eq!(ms_to_ticks(1000), frequency() as u64);
+}
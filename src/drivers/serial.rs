@@ -16,16 +16,19 @@
 //?
 
 //!
-//! A driver used to send information through a Serial port, such as through Qemu to write to an
-//! external terminal.
+//! A driver used to send information through the standard serial ports (COM1 through COM4), such as
+//! through Qemu to write to an external terminal, and to receive input from said terminal.
 //!
 
 use core::fmt::{ self, Write };
+use core::sync::atomic::{ AtomicBool, Ordering };
 
-use uart_16550::SerialPort;
-use spin::Mutex;
+use x86_64::instructions::port::Port;
 use lazy_static::lazy_static;
 
+use crate::instructions::interrupts::{ self, InterruptIndex, IrqError };
+use crate::sync::{ irq_mutex::{ IrqMutex, IrqMutexGuard }, ring_buffer::RingBuffer };
+
 
 /*
  * Constant & Static
@@ -33,17 +36,482 @@ use lazy_static::lazy_static;
  */
 
 
-/// The standard port number for the first serial interface.
-const SERIAL_1_PORT: u16 = 0x3F8;
+/// The frequency of the UART's input clock divided by 16, which is the baud rate at a divisor of 1.
+pub const MAX_BAUD_RATE: u32 = 115_200;
+
+/// The offsets of the UART's registers from its base port.
+const DATA_OFFSET:          u16 = 0;    // Receive/transmit buffer, or the divisor's low byte if DLAB is set.
+const INTERRUPT_OFFSET:     u16 = 1;    // Interrupt enable, or the divisor's high byte if DLAB is set.
+const FIFO_CONTROL_OFFSET:  u16 = 2;
+const LINE_CONTROL_OFFSET:  u16 = 3;
+const MODEM_CONTROL_OFFSET: u16 = 4;
+const LINE_STATUS_OFFSET:   u16 = 5;
+
+/// The line control bit which maps the data and interrupt registers onto the baud rate divisor.
+const LINE_CONTROL_DLAB: u8 = 0x80;
+
+/// Enables and clears both FIFOs, with a 14-byte receive interrupt threshold.
+const FIFO_CONTROL_ENABLE: u8 = 0xC7;
+
+/// Asserts DTR and RTS, along with OUT2 which gates the UART's interrupt line.
+const MODEM_CONTROL_NORMAL: u8 = 0x0B;
+
+/// Loops the UART's output back into its input, with RTS, OUT1 and OUT2 asserted.
+const MODEM_CONTROL_LOOPBACK: u8 = 0x1E;
+
+/// The byte sent whilst looped back to probe whether a UART is present.
+const PROBE_BYTE: u8 = 0xAE;
+
+/// Enables the received data available interrupt.
+const INTERRUPT_RECEIVED_DATA: u8 = 0x01;
+
+/// The line status bits which are set whilst received data is waiting to be read, and whilst the
+/// transmit buffer is empty.
+const LINE_STATUS_DATA_READY:     u8 = 0x01;
+const LINE_STATUS_TRANSMIT_EMPTY: u8 = 0x20;
+
+/// The amount of line status polls after which a UART that never becomes ready to transmit, or
+/// never receives its own probe, is treated as absent rather than hanging the kernel.
+const TRANSMIT_TIMEOUT: usize = 100_000;
+
+/// The maximum amount of received bytes that may be buffered per port before further bytes are
+/// dropped.
+pub const RECEIVE_BUFFER_SIZE: usize = 256;
+
+/// The bytes received on each serial port, awaiting a reader.
+static RECEIVED: [RingBuffer<u8, RECEIVE_BUFFER_SIZE>; 4] = [const { RingBuffer::new() }; 4];
+
+/// Whether each serial port was found to be present, readable without locking the port.
+static PRESENT: [AtomicBool; 4] = [const { AtomicBool::new(false) }; 4];
+
+/// Whether the last byte consumed by `read_line` on each serial port was a carriage return, so that
+/// the line feed of a CRLF pair is not mistaken for an empty line.
+static LAST_WAS_CR: [AtomicBool; 4] = [const { AtomicBool::new(false) }; 4];
 
 lazy_static! {
 
     /// A publicly accessible serial port for the first serial interface.
-    pub static ref SERIAL_1: Mutex<SerialPort> = {
-        let mut serial_port: SerialPort = unsafe { SerialPort::new(SERIAL_1_PORT) };
-        serial_port.init();
-        Mutex::new(serial_port)
+    pub static ref SERIAL_1: IrqMutex<Uart> = IrqMutex::new(Uart::open(SerialPortId::Com1));
+
+    /// A publicly accessible serial port for the second serial interface.
+    pub static ref SERIAL_2: IrqMutex<Uart> = IrqMutex::new(Uart::open(SerialPortId::Com2));
+
+    /// A publicly accessible serial port for the third serial interface.
+    pub static ref SERIAL_3: IrqMutex<Uart> = IrqMutex::new(Uart::open(SerialPortId::Com3));
+
+    /// A publicly accessible serial port for the fourth serial interface.
+    pub static ref SERIAL_4: IrqMutex<Uart> = IrqMutex::new(Uart::open(SerialPortId::Com4));
+}
+
+
+/*
+ * Serial Port
+ *      Identifiers
+ */
+
+
+/// Encapsulates the four standard serial ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialPortId {
+    Com1,
+    Com2,
+    Com3,
+    Com4
+}
+
+impl SerialPortId {
+
+    /// Every serial port, in order.
+    pub const ALL: [Self; 4] = [Self::Com1, Self::Com2, Self::Com3, Self::Com4];
+
+    /// Gets the standard base I/O port of this serial port.
+    pub fn base_port(self) -> u16 {
+        match self {
+            Self::Com1 => 0x3F8,
+            Self::Com2 => 0x2F8,
+            Self::Com3 => 0x3E8,
+            Self::Com4 => 0x2E8
+        }
+    }
+
+    /// Gets the IRQ line this serial port raises, which COM3 and COM4 share with COM1 and COM2.
+    pub fn irq(self) -> InterruptIndex {
+        match self {
+            Self::Com1 | Self::Com3 => InterruptIndex::Com1,
+            Self::Com2 | Self::Com4 => InterruptIndex::Com2
+        }
+    }
+
+    /// Gets the locked driver of this serial port.
+    pub fn port(self) -> &'static IrqMutex<Uart> {
+        match self {
+            Self::Com1 => &SERIAL_1,
+            Self::Com2 => &SERIAL_2,
+            Self::Com3 => &SERIAL_3,
+            Self::Com4 => &SERIAL_4
+        }
+    }
+
+    /// Whether this serial port was found to be present when it was probed.
+    pub fn is_present(self) -> bool {
+        PRESENT[self as usize].load(Ordering::Acquire)
+    }
+
+    /// Pops the oldest byte received on this serial port, if there is one.
+    pub fn try_read_byte(self) -> Option<u8> {
+        RECEIVED[self as usize].pop()
+    }
+
+    /// Halts the CPU until a byte is received on this serial port, and pops it.
+    /// # Panics
+    /// Panics if interrupts are disabled, as no byte could ever arrive.
+    pub fn read_byte(self) -> u8 {
+        assert!(x86_64::instructions::interrupts::are_enabled(), "Cannot wait for serial input whilst interrupts are disabled");
+        loop {
+            if let Some(byte) = self.try_read_byte() {
+                return byte;
+            }
+            x86_64::instructions::hlt();
+        }
+    }
+
+    /// Reads a single line received on this serial port into the buffer, blocking until it is
+    /// terminated, and returns the amount of bytes read.
+    /// # Note
+    /// Lines may be terminated by either a line feed, a carriage return or both, and the terminator
+    /// is not stored. Backspace and delete remove the last byte read. If the buffer fills up before
+    /// the line is terminated, the partial line is returned and the rest is left for the next read.
+    /// # Panics
+    /// Panics if interrupts are disabled, as no byte could ever arrive.
+    pub fn read_line(self, buffer: &mut [u8]) -> usize {
+        let mut len: usize = 0;
+        while len < buffer.len() {
+            let byte:   u8   = self.read_byte();
+            let was_cr: bool = LAST_WAS_CR[self as usize].swap(byte == b'\r', Ordering::Relaxed);
+            match byte {
+                b'\n' if was_cr => continue,
+                b'\n' | b'\r'  => break,
+                0x08 | 0x7F    => len = len.saturating_sub(1),
+                _              => {
+                    buffer[len] = byte;
+                    len += 1;
+                }
+            }
+        }
+        len
+    }
+}
+
+
+/*
+ * Line
+ *      Configuration
+ */
+
+
+/// The amount of data bits in each character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    Eight
+}
+
+/// The parity bit appended to each character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+    Mark,
+    Space
+}
+
+/// The amount of stop bits appended to each character.
+/// # Note
+/// With five data bits, `Two` produces one and a half stop bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopBits {
+    One,
+    Two
+}
+
+/// Describes the baud rate and line format a serial port is configured with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialConfig {
+    
+    /// The divisor applied to `MAX_BAUD_RATE` to produce the baud rate. Must not be zero.
+    pub baud_divisor: u16,
+    pub data_bits:    DataBits,
+    pub parity:       Parity,
+    pub stop_bits:    StopBits
+}
+
+impl SerialConfig {
+
+    /// The configuration every serial port is opened with: 38400 baud, 8 data bits, no parity and
+    /// one stop bit.
+    pub const DEFAULT: Self = SerialConfig {
+        baud_divisor: 3,
+        data_bits:    DataBits::Eight,
+        parity:       Parity::None,
+        stop_bits:    StopBits::One
     };
+
+    /// Creates an 8N1 configuration running at the given baud rate, if it can be produced exactly
+    /// by a divisor of `MAX_BAUD_RATE`.
+    pub fn from_baud_rate(baud_rate: u32) -> Option<Self> {
+        if baud_rate == 0 || MAX_BAUD_RATE % baud_rate != 0 {
+            return None;
+        }
+        
+        let baud_divisor: u16 = u16::try_from(MAX_BAUD_RATE / baud_rate).ok()?;
+        Some(SerialConfig { baud_divisor, ..Self::DEFAULT })
+    }
+
+    /// Gets the baud rate this configuration runs at.
+    pub fn baud_rate(&self) -> u32 {
+        MAX_BAUD_RATE / self.baud_divisor.max(1) as u32
+    }
+
+    /// Encodes the line format into the UART's line control register.
+    pub fn line_control(&self) -> u8 {
+        let data_bits: u8 = match self.data_bits {
+            DataBits::Five  => 0b00,
+            DataBits::Six   => 0b01,
+            DataBits::Seven => 0b10,
+            DataBits::Eight => 0b11
+        };
+        let stop_bits: u8 = match self.stop_bits {
+            StopBits::One => 0b0,
+            StopBits::Two => 0b1
+        };
+        let parity: u8 = match self.parity {
+            Parity::None  => 0b000,
+            Parity::Odd   => 0b001,
+            Parity::Even  => 0b011,
+            Parity::Mark  => 0b101,
+            Parity::Space => 0b111
+        };
+        
+        data_bits | stop_bits << 2 | parity << 3
+    }
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+
+/*
+ * UART
+ *      Driver
+ */
+
+
+/// The errors that may occur whilst configuring a serial port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialError {
+
+    /// No UART responded on the serial port's I/O ports.
+    NotPresent,
+
+    /// The configuration's baud divisor was zero.
+    InvalidBaudDivisor
+}
+
+/// A driver for a single 16550-compatible UART.
+/// # Note
+/// Writes to a port that was not found to be present are silently discarded.
+pub struct Uart {
+    id:     SerialPortId,
+    config: SerialConfig
+}
+
+impl Uart {
+
+    /// Opens a serial port, probing whether it is present and, if so, configuring it with the
+    /// default configuration and enabling its received data interrupt.
+    /// # Note
+    /// This is the only place a port is probed, as probing clears its FIFOs.
+    fn open(id: SerialPortId) -> Self {
+        let mut uart: Uart = Uart { id, config: SerialConfig::DEFAULT };
+
+        // Clear DLAB first, so that the interrupt enable register is addressed rather than the divisor.
+        unsafe {
+            uart.register(LINE_CONTROL_OFFSET).write(SerialConfig::DEFAULT.line_control());
+            uart.register(INTERRUPT_OFFSET).write(0x00);
+        }
+        if uart.probe() && uart.configure(SerialConfig::DEFAULT).is_ok() {
+            unsafe { uart.register(INTERRUPT_OFFSET).write(INTERRUPT_RECEIVED_DATA) };
+        }
+        uart
+    }
+
+    /// Gets the identifier of this serial port.
+    pub fn id(&self) -> SerialPortId {
+        self.id
+    }
+
+    /// Gets the configuration this serial port is currently running with.
+    pub fn config(&self) -> SerialConfig {
+        self.config
+    }
+
+    /// Whether this serial port was found to be present when it was last probed.
+    pub fn is_present(&self) -> bool {
+        self.id.is_present()
+    }
+
+    /// Probes whether a UART is present by looping it back onto itself and checking that a byte
+    /// sent is received unchanged.
+    /// # Note
+    /// Both FIFOs are cleared, and the UART is left in its normal operating mode with its interrupts
+    /// restored.
+    pub fn probe(&mut self) -> bool {
+        let mut interrupts:  Port<u8> = self.register(INTERRUPT_OFFSET);
+        let mut line_status: Port<u8> = self.register(LINE_STATUS_OFFSET);
+        
+        let present: bool = unsafe {
+            let enabled: u8 = interrupts.read();
+            interrupts.write(0x00);
+            self.register(FIFO_CONTROL_OFFSET).write(FIFO_CONTROL_ENABLE);
+            self.register(MODEM_CONTROL_OFFSET).write(MODEM_CONTROL_LOOPBACK);
+            self.register(DATA_OFFSET).write(PROBE_BYTE);
+
+            let received: bool = (0..TRANSMIT_TIMEOUT).any(|_| line_status.read() & LINE_STATUS_DATA_READY != 0);
+            let echoed:   bool = received && self.register(DATA_OFFSET).read() == PROBE_BYTE;
+            
+            self.register(MODEM_CONTROL_OFFSET).write(MODEM_CONTROL_NORMAL);
+            interrupts.write(enabled);
+            echoed
+        };
+        PRESENT[self.id as usize].store(present, Ordering::Release);
+        present
+    }
+
+    /// Reprograms the baud rate and line format of a serial port that was found to be present.
+    /// # Note
+    /// The FIFOs are left untouched, so that no byte waiting to be read is discarded.
+    pub fn configure(&mut self, config: SerialConfig) -> Result<(), SerialError> {
+        if config.baud_divisor == 0 {
+            return Err(SerialError::InvalidBaudDivisor);
+        }
+        if !self.is_present() {
+            return Err(SerialError::NotPresent);
+        }
+
+        // Setting DLAB maps the divisor over the data and interrupt enable registers until the line
+        // format is written, which clears it again.
+        unsafe {
+            self.register(LINE_CONTROL_OFFSET).write(LINE_CONTROL_DLAB);
+            self.register(DATA_OFFSET).write(config.baud_divisor as u8);
+            self.register(INTERRUPT_OFFSET).write((config.baud_divisor >> 8) as u8);
+            self.register(LINE_CONTROL_OFFSET).write(config.line_control());
+        }
+        self.config = config;
+        Ok(())
+    }
+
+    /// Sends a single byte, waiting for the transmit buffer to empty first.
+    /// # Note
+    /// If the transmit buffer never empties, the port is marked as absent rather than hanging.
+    pub fn send(&mut self, byte: u8) -> () {
+        if !self.is_present() {
+            return;
+        }
+
+        let mut line_status: Port<u8> = self.register(LINE_STATUS_OFFSET);
+        for _ in 0..TRANSMIT_TIMEOUT {
+            if unsafe { line_status.read() } & LINE_STATUS_TRANSMIT_EMPTY != 0 {
+                unsafe { self.register(DATA_OFFSET).write(byte) };
+                return;
+            }
+            core::hint::spin_loop();
+        }
+        PRESENT[self.id as usize].store(false, Ordering::Release);
+    }
+
+    /// Gets one of the UART's registers by its offset from the base port.
+    fn register(&self, offset: u16) -> Port<u8> {
+        Port::new(self.id.base_port() + offset)
+    }
+}
+
+impl fmt::Write for Uart {
+
+    /// Sends a whole string through the serial port.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.send(byte);
+        }
+        Ok(())
+    }
+}
+
+
+/*
+ * Receive
+ *      Handling
+ */
+
+
+/// Opens every serial port, and registers the receive handler on the IRQ lines of those which are
+/// present, so that bytes sent to them are buffered.
+pub fn init() -> Result<(), IrqError> {
+    for id in SerialPortId::ALL {
+        let _: &IrqMutex<Uart> = id.port();    // Opening the port probes it.
+        if !id.is_present() {
+            continue;
+        }
+
+        match interrupts::register_irq(id.irq(), receive_handler) {
+            Ok(()) | Err(IrqError::AlreadyRegistered) => (),
+            Err(error)                                => return Err(error)
+        }
+    }
+    Ok(())
+}
+
+/// Handles a received data interrupt by draining every byte waiting in each present UART sharing
+/// the raised line into its receive buffer.
+/// # Note
+/// Each driver is locked whilst it is drained, so that a reconfiguration can never be interrupted
+/// with the divisor mapped over the data register. This cannot deadlock, as the interrupt cannot
+/// arrive whilst a driver's lock is held. Bytes are silently dropped if a buffer is full.
+fn receive_handler(index: InterruptIndex) -> () {
+    for id in SerialPortId::ALL.into_iter().filter(|id| id.irq() == index && id.is_present()) {
+        let     uart:        IrqMutexGuard<Uart> = id.port().lock();
+        let mut data:        Port<u8>            = uart.register(DATA_OFFSET);
+        let mut line_status: Port<u8>            = uart.register(LINE_STATUS_OFFSET);
+
+        while unsafe { line_status.read() } & LINE_STATUS_DATA_READY != 0 {
+            let _ = RECEIVED[id as usize].push(unsafe { data.read() });
+        }
+    }
+}
+
+/// Pops the oldest byte received on the first serial interface, if there is one.
+pub fn try_read_byte() -> Option<u8> {
+    SerialPortId::Com1.try_read_byte()
+}
+
+/// Halts the CPU until a byte is received on the first serial interface, and pops it.
+/// # Panics
+/// Panics if interrupts are disabled, as no byte could ever arrive.
+pub fn read_byte() -> u8 {
+    SerialPortId::Com1.read_byte()
+}
+
+/// Reads a single line received on the first serial interface into the buffer, blocking until it
+/// is terminated, and returns the amount of bytes read.
+/// # Panics
+/// Panics if interrupts are disabled, as no byte could ever arrive.
+pub fn read_line(buffer: &mut [u8]) -> usize {
+    SerialPortId::Com1.read_line(buffer)
 }
 
 
@@ -69,8 +537,125 @@ macro_rules! serial_println {
     ($fmt:expr, $($arg:tt)*) => ($crate::serial_print!(concat!($fmt, "\n"), $($arg)*));
 }
 
+/// Prints to the host through the given serial port.
+#[macro_export]
+macro_rules! serial_print_to {
+    ($id:expr, $($arg:tt)*) => {
+        $crate::drivers::serial::_print_to($id, format_args!($($arg)*));
+    };
+}
+
+/// Prints to the host through the given serial port, appending a newline.
+#[macro_export]
+macro_rules! serial_println_to {
+    ($id:expr)                         => ($crate::serial_print_to!($id, "\n"));
+    ($id:expr, $fmt:expr)              => ($crate::serial_print_to!($id, concat!($fmt, "\n")));
+    ($id:expr, $fmt:expr, $($arg:tt)*) => ($crate::serial_print_to!($id, concat!($fmt, "\n"), $($arg)*));
+}
+
 /// A global print function to stream information through the serial port.
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments) {
-    SERIAL_1.lock().write_fmt(args).expect("Printing to serial failed");
+    _print_to(SerialPortId::Com1, args);
+}
+
+/// A global print function to stream information through the given serial port.
+#[doc(hidden)]
+pub fn _print_to(id: SerialPortId, args: fmt::Arguments) {
+    id.port().lock().write_fmt(args).expect("Printing to serial failed");
+}
+
+/// Prints through the first serial port even if it is already being written to, such as by code
+/// that an exception or panic interrupted, where waiting for the lock would deadlock.
+/// # Safety
+/// Whatever was writing to the port must not resume until this returns. Its output may be split
+/// by this output, but the port itself is left usable.
+pub unsafe fn force_print(args: fmt::Arguments) -> () {
+    let _ = SERIAL_1.force_lock().write_fmt(args);
+}
+
+
+/*
+ * Serial Driver
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_receive_loopback() -> () {
+    use crate::drivers::pit;
+    use crate::sync::irq_mutex::IrqMutexGuard;
+
+    let mut uart:          IrqMutexGuard<Uart> = SERIAL_1.lock();
+    let mut modem_control: Port<u8>            = uart.register(MODEM_CONTROL_OFFSET);
+    while try_read_byte().is_some() {}
+
+    // Whilst looped back, everything sent is received instead of reaching the host.
+    unsafe { modem_control.write(MODEM_CONTROL_LOOPBACK) };
+    for byte in b"ping\r\nok\n" {
+        uart.send(*byte);
+    }
+    drop(uart);    // The lock holds interrupts off, so it must be released before waiting.
+    
+    let deadline: u64 = pit::ticks() + pit::ms_to_ticks(100);
+    while RECEIVED[SerialPortId::Com1 as usize].len() < 9 && pit::ticks() < deadline {
+        x86_64::instructions::hlt();
+    }
+    unsafe { modem_control.write(MODEM_CONTROL_NORMAL) };
+
+    let mut line: [u8; 16] = [0; 16];
+    let     len:  usize    = read_line(&mut line);
+    assert_eq!(&line[..len], b"ping");
+    let len: usize = read_line(&mut line);
+    assert_eq!(&line[..len], b"ok");
+}
+
+#[test_case]
+fn test_port_probing() -> () {
+    assert!(SerialPortId::Com1.is_present());
+    assert!(SerialPortId::Com2.is_present());
+    assert!(!SerialPortId::Com4.is_present());
+
+    // Writing to an absent port must not hang.
+    serial_println_to!(SerialPortId::Com4, "This goes nowhere.");
+    assert_eq!(SERIAL_4.lock().configure(SerialConfig::DEFAULT), Err(SerialError::NotPresent));
+}
+
+#[test_case]
+fn test_line_configuration() -> () {
+    let config: SerialConfig = SerialConfig {
+        data_bits: DataBits::Seven,
+        parity:    Parity::Even,
+        stop_bits: StopBits::Two,
+        ..SerialConfig::from_baud_rate(9600).unwrap()
+    };
+    assert_eq!(config.baud_divisor,   12);
+    assert_eq!(config.line_control(), 0b0001_1110);
+    assert_eq!(SerialConfig::DEFAULT.line_control(), 0b0000_0011);
+    assert_eq!(SerialConfig::from_baud_rate(1000), None);
+
+    let mut uart: crate::sync::irq_mutex::IrqMutexGuard<Uart> = SERIAL_2.lock();
+    uart.configure(config).unwrap();
+    assert_eq!(unsafe { uart.register(LINE_CONTROL_OFFSET).read() }, config.line_control());
+    assert_eq!(uart.config().baud_rate(), 9600);
+    uart.configure(SerialConfig::DEFAULT).unwrap();
+}
+
+#[test_case]
+fn test_configure_keeps_received_bytes() -> () {
+    let mut uart:          crate::sync::irq_mutex::IrqMutexGuard<Uart> = SERIAL_2.lock();
+    let mut modem_control: Port<u8>                                    = uart.register(MODEM_CONTROL_OFFSET);
+    let mut line_status:   Port<u8>                                    = uart.register(LINE_STATUS_OFFSET);
+
+    // Interrupts are disabled whilst the port is locked, so the looped back byte stays in the FIFO.
+    unsafe {
+        modem_control.write(MODEM_CONTROL_LOOPBACK);
+        uart.register(DATA_OFFSET).write(PROBE_BYTE);
+    }
+    assert!((0..TRANSMIT_TIMEOUT).any(|_| unsafe { line_status.read() } & LINE_STATUS_DATA_READY != 0));
+
+    uart.configure(SerialConfig::DEFAULT).unwrap();
+    assert_ne!(unsafe { line_status.read() } & LINE_STATUS_DATA_READY, 0, "Configuring the port discarded a received byte");
+    assert_eq!(unsafe { uart.register(DATA_OFFSET).read() }, PROBE_BYTE);
+    unsafe { modem_control.write(MODEM_CONTROL_NORMAL) };
 }
@@ -23,7 +23,13 @@ use core::fmt::{ self, Write };
 
 use volatile::Volatile;
 use lazy_static::lazy_static;
-use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+use crate::sync::irq_mutex::IrqMutex;
+#[cfg(test)]
+use crate::sync::irq_mutex::IrqMutexGuard;
+
+use super::cp437;
 
 
 /*
@@ -41,10 +47,45 @@ const BUFFER_WIDTH: usize = 80;
 /// The pointer to the VGA buffer which encompasses it safetly.
 const VGA_BUFFER: *mut VGABuffer = 0xb8000 as *mut VGABuffer;
 
+/// The CRT controller's ports, through which its registers are selected and then accessed.
+const CRTC_INDEX_PORT: u16 = 0x3d4;
+const CRTC_DATA_PORT:  u16 = 0x3d5;
+
+/// The CRT controller's cursor registers.
+const CURSOR_START_REGISTER:         u8 = 0x0a;
+const CURSOR_END_REGISTER:           u8 = 0x0b;
+const CURSOR_LOCATION_HIGH_REGISTER: u8 = 0x0e;
+const CURSOR_LOCATION_LOW_REGISTER:  u8 = 0x0f;
+
+/// The bit of the cursor start register which hides the cursor.
+const CURSOR_DISABLE: u8 = 1 << 5;
+
+/// The highest scanline of a character cell, which bounds the cursor's shape.
+pub const MAX_SCANLINE: u8 = 15;
+
+/// The distance between each tab stop.
+const TAB_WIDTH: usize = 8;
+
+/// The backspace character.
+const BACKSPACE: u8 = 0x08;
+
+/// The form feed character.
+const FORM_FEED: u8 = 0x0c;
+
+/// The escape character, which begins every ANSI escape sequence.
+const ESCAPE: u8 = 0x1b;
+
+/// The cancel and substitute characters, which abort an escape sequence.
+const CANCEL:     u8 = 0x18;
+const SUBSTITUTE: u8 = 0x1a;
+
+/// The maximum amount of parameters a control sequence may carry; any beyond are ignored.
+const MAX_CSI_PARAMS: usize = 8;
+
 lazy_static! {
     
     /// A global static reference to the VGA text mode drivers.
-    pub static ref WRITER: Mutex<VGADriver> = Mutex::new(VGADriver::new(VGAColourDesc::new(VGAColourFull::White, VGAColour::Black, false)));
+    pub static ref WRITER: IrqMutex<VGADriver> = IrqMutex::new(VGADriver::new(VGAColourDesc::new(VGAColourFull::White, VGAColour::Black, false)));
 }
 
 
@@ -68,6 +109,22 @@ pub enum VGAColour {
 }
 
 impl VGAColour {
+
+    /// Converts an ANSI colour index (0 through 7) into its colour, as ANSI orders its colours
+    /// differently to VGA.
+    pub fn from_ansi(index: u16) -> Option<Self> {
+        match index {
+            0 => Some(Self::Black),
+            1 => Some(Self::Red),
+            2 => Some(Self::Green),
+            3 => Some(Self::Brown),
+            4 => Some(Self::Blue),
+            5 => Some(Self::Magenta),
+            6 => Some(Self::Cyan),
+            7 => Some(Self::LightGrey),
+            _ => None
+        }
+    }
     
     /// Converts the enum into a 4-bit colour channel.
     pub fn to_bits(&self) -> u8 {
@@ -107,6 +164,28 @@ pub enum VGAColourFull {
 
 impl VGAColourFull {
     
+    /// Converts a raw colour and whether it is the light variant into the full colour.
+    pub fn from_raw(colour: VGAColour, light: bool) -> Self {
+        match (colour, light) {
+            (VGAColour::Black,     false) => Self::Black,
+            (VGAColour::Blue,      false) => Self::Blue,
+            (VGAColour::Green,     false) => Self::Green,
+            (VGAColour::Cyan,      false) => Self::Cyan,
+            (VGAColour::Red,       false) => Self::Red,
+            (VGAColour::Magenta,   false) => Self::Magenta,
+            (VGAColour::Brown,     false) => Self::Brown,
+            (VGAColour::LightGrey, false) => Self::LightGrey,
+            (VGAColour::Black,     true)  => Self::DarkGray,
+            (VGAColour::Blue,      true)  => Self::LightBlue,
+            (VGAColour::Green,     true)  => Self::LightGreen,
+            (VGAColour::Cyan,      true)  => Self::LightCyan,
+            (VGAColour::Red,       true)  => Self::LightRed,
+            (VGAColour::Magenta,   true)  => Self::Pink,
+            (VGAColour::Brown,     true)  => Self::Yellow,
+            (VGAColour::LightGrey, true)  => Self::White
+        }
+    }
+    
     /// Converts the VGAColourFull type into its raw colour and whether then colour is the light
     /// variant of the raw colour or not.
     pub fn to_raw(&self) -> (VGAColour, bool) {
@@ -166,6 +245,51 @@ impl VGAColourDesc {
         
         VGAColourDesc((background_4b as u8) << 4 | (foreground_4b as u8))
     }
+
+    /// Replaces the foreground colour.
+    pub fn with_foreground(self, foreground: VGAColourFull) -> Self {
+        let (colour, light): (VGAColour, bool) = foreground.to_raw();
+        VGAColourDesc((self.0 & 0xf0) | colour.to_bits() | (light as u8) << 3)
+    }
+
+    /// Replaces the background colour.
+    pub fn with_background(self, background: VGAColour) -> Self {
+        VGAColourDesc((self.0 & 0x8f) | background.to_bits() << 4)
+    }
+
+    /// Replaces the blink status.
+    pub fn with_blink(self, blink: bool) -> Self {
+        VGAColourDesc((self.0 & 0x7f) | (blink as u8) << 7)
+    }
+
+    /// Gets the foreground colour.
+    pub fn foreground(&self) -> VGAColourFull {
+        VGAColourFull::from_raw(raw_colour(self.0 & 0x7), self.0 & 0x8 != 0)
+    }
+
+    /// Gets the background colour.
+    pub fn background(&self) -> VGAColour {
+        raw_colour((self.0 >> 4) & 0x7)
+    }
+
+    /// Gets the blink status.
+    pub fn blink(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+}
+
+/// Converts a 3-bit colour channel back into its raw colour.
+fn raw_colour(bits: u8) -> VGAColour {
+    match bits & 0x7 {
+        0x0 => VGAColour::Black,
+        0x1 => VGAColour::Blue,
+        0x2 => VGAColour::Green,
+        0x3 => VGAColour::Cyan,
+        0x4 => VGAColour::Red,
+        0x5 => VGAColour::Magenta,
+        0x6 => VGAColour::Brown,
+        _   => VGAColour::LightGrey
+    }
 }
 
 
@@ -196,9 +320,18 @@ struct VGABuffer {
 
 /// A full VGA driver that encapsulates the VGA text buffer region in memory and allows for the
 /// safe utilization of said display feature for printing text.
+/// # Note
+/// Text is written at a row and column cursor, which starts out on the bottom row so that output
+/// scrolls up from the bottom of the screen.
 pub struct VGADriver {
+    row_position:    usize,
     column_position: usize,
     colour_desc:     VGAColourDesc,
+    default_colour:  VGAColourDesc,                  // The colour restored by an SGR reset.
+    bold:            bool,                           // Whether foreground colours are brightened.
+    saved_cursor:    (usize, usize, VGAColourDesc),
+    escape:          EscapeState,
+    wrapped_rows:    [bool; BUFFER_HEIGHT],          // Rows which continue the row above them.
     buffer:          &'static mut VGABuffer
 }
 
@@ -207,51 +340,260 @@ impl VGADriver {
     /// Creates a new VGA driver to access the VGA buffer.
     pub fn new(desc: VGAColourDesc) -> Self {
         VGADriver {
+            row_position:    BUFFER_HEIGHT - 1,
             column_position: 0,
             colour_desc:     desc,
+            default_colour:  desc,
+            bold:            false,
+            saved_cursor:    (BUFFER_HEIGHT - 1, 0, desc),
+            escape:          EscapeState::Ground,
+            wrapped_rows:    [false; BUFFER_HEIGHT],
             buffer:          unsafe { &mut *VGA_BUFFER }
         }
     }
     
     /// Writes a single byte onto the VGA buffer.
+    /// # Note
+    /// Newline, carriage return, tab, backspace and form feed are acted upon rather than drawn.
     pub fn write_byte(&mut self, byte: u8) -> () {
-        match byte {
-            b'\n' => self.new_line(),
-            _     => {
-                if self.column_position >= BUFFER_WIDTH {
-                    self.new_line();
-                }
+        self.put_byte(byte);
+        self.sync_cursor();
+    }
 
-                let row:  usize         = BUFFER_HEIGHT - 1;
-                let col:  usize         = self.column_position;
-                let desc: VGAColourDesc = self.colour_desc;
+    /// Writes raw bytes onto the VGA buffer as code page 437 glyphs, acting upon control characters
+    /// as `write_byte` does. Unlike `write_str`, the bytes need not be valid UTF-8, and escape
+    /// sequences are not interpreted.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> () {
+        for byte in bytes {
+            self.put_byte(*byte);
+        }
+        self.sync_cursor();
+    }
 
-                self.buffer.chars[row][col].write(VGAChar {
-                    char: byte,
-                    desc
-                });
-                self.column_position += 1;
-            }
+    /// Writes a single byte onto the VGA buffer without moving the hardware cursor, so that a whole
+    /// string may be written before the cursor is synchronized once.
+    fn put_byte(&mut self, byte: u8) -> () {
+        match byte {
+            b'\n'      => self.new_line(),
+            b'\r'      => self.column_position = 0,
+            b'\t'      => self.column_position = ((self.column_position / TAB_WIDTH + 1) * TAB_WIDTH).min(BUFFER_WIDTH),
+            BACKSPACE  => self.backspace(),
+            FORM_FEED  => self.clear(),
+            _          => self.draw_byte(byte)
         }
     }
 
-    /// Clears the whole buffer.
+    /// Draws a single glyph onto the VGA buffer at the cursor, wrapping onto the next row if the
+    /// current row is full. Unlike `write_byte`, control characters are drawn as their glyphs, and
+    /// the hardware cursor is left for the caller to synchronize.
+    fn draw_byte(&mut self, byte: u8) -> () {
+        if self.column_position >= BUFFER_WIDTH {
+            self.new_line();
+            self.wrapped_rows[self.row_position] = true;
+        }
+
+        let row:  usize         = self.row_position;
+        let col:  usize         = self.column_position;
+        let desc: VGAColourDesc = self.colour_desc;
+
+        self.buffer.chars[row][col].write(VGAChar {
+            char: byte,
+            desc
+        });
+        self.column_position += 1;
+    }
+
+    /// Clears the whole buffer, moving the cursor to the top left.
     pub fn clear(&mut self) -> () {
         for row in 0..BUFFER_HEIGHT {
             self.clear_row(row);
         }
+        self.wrapped_rows    = [false; BUFFER_HEIGHT];
+        self.row_position    = 0;
+        self.column_position = 0;
+        self.sync_cursor();
+    }
+
+    /// Gets the colour that characters are written in.
+    pub fn colour(&self) -> VGAColourDesc {
+        self.colour_desc
+    }
+
+    /// Replaces the colour that characters are written in.
+    pub fn set_colour(&mut self, colour: VGAColourDesc) -> () {
+        self.colour_desc = colour;
+    }
+
+    /// Gets the row and column that the next character will be written at.
+    /// # Note
+    /// Once a row has been filled, the column is `BUFFER_WIDTH` until the next character wraps onto
+    /// the following row.
+    pub fn position(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    /// Moves the cursor to the given row and column. A column of `BUFFER_WIDTH` marks the row as
+    /// filled, as reported by `position`, so that the next character wraps onto the following row.
+    /// # Panics
+    /// Panics if the position lies outside of the buffer.
+    pub fn set_position(&mut self, row: usize, col: usize) -> () {
+        assert!(row < BUFFER_HEIGHT && col <= BUFFER_WIDTH, "Position ({}, {}) lies outside of the VGA buffer", row, col);
+        
+        self.row_position    = row;
+        self.column_position = col;
+        self.sync_cursor();
+    }
+
+    /// Writes a string starting at the given row and column in the given colour, without moving the
+    /// cursor. Anything past the end of the row is cut off.
+    /// # Panics
+    /// Panics if the position lies outside of the buffer.
+    pub fn write_at(&mut self, row: usize, col: usize, s: &str, colour: VGAColourDesc) -> () {
+        assert!(row < BUFFER_HEIGHT && col < BUFFER_WIDTH, "Position ({}, {}) lies outside of the VGA buffer", row, col);
+        
+        for (col, char) in (col..BUFFER_WIDTH).zip(s.chars()) {
+            self.buffer.chars[row][col].write(VGAChar {
+                char: cp437::encode_or_unmappable(char),
+                desc: colour
+            });
+        }
+    }
+
+    /// Reads the character at the given row and column.
+    pub fn char_at(&self, row: usize, col: usize) -> u8 {
+        self.buffer.chars[row][col].read().char
+    }
+
+    /// Reads the colour of the cell at the given row and column.
+    pub fn colour_at(&self, row: usize, col: usize) -> VGAColourDesc {
+        self.buffer.chars[row][col].read().desc
+    }
+
+    /// Fills a rectangular region with the given character and colour.
+    /// # Panics
+    /// Panics if the region does not fit within the buffer.
+    pub fn fill_region(&mut self, top: usize, left: usize, height: usize, width: usize, char: u8, colour: VGAColourDesc) -> () {
+        assert_region(top, left, height, width);
+        
+        self.fill_cells(top, left, height, width, char, colour);
+        self.wrapped_rows[top..top + height].fill(false);
+    }
+
+    /// Scrolls the contents of a rectangular region up by the given amount of lines, or down if the
+    /// amount is negative. The lines uncovered by the scroll are cleared.
+    /// # Panics
+    /// Panics if the region does not fit within the buffer.
+    pub fn scroll_region(&mut self, top: usize, left: usize, height: usize, width: usize, lines: isize) -> () {
+        assert_region(top, left, height, width);
+        
+        self.shift_cells(top, left, height, width, lines);
+        self.wrapped_rows[top..top + height].fill(false);
+    }
+
+    /// Shows the hardware cursor.
+    pub fn show_cursor(&mut self) -> () {
+        let start: u8 = crtc_read(CURSOR_START_REGISTER);
+        crtc_write(CURSOR_START_REGISTER, start & !CURSOR_DISABLE);
+    }
+
+    /// Hides the hardware cursor.
+    pub fn hide_cursor(&mut self) -> () {
+        let start: u8 = crtc_read(CURSOR_START_REGISTER);
+        crtc_write(CURSOR_START_REGISTER, start | CURSOR_DISABLE);
+    }
+
+    /// Reshapes the hardware cursor to span from the start scanline to the end scanline of a cell,
+    /// where scanline 0 is the top of the cell.
+    /// # Panics
+    /// Panics if either scanline exceeds `MAX_SCANLINE`.
+    pub fn set_cursor_shape(&mut self, start: u8, end: u8) -> () {
+        assert!(start <= MAX_SCANLINE && end <= MAX_SCANLINE, "Cursor scanlines must not exceed {}", MAX_SCANLINE);
+        
+        // Only the low five bits hold the scanline; the rest must be preserved.
+        let start_register: u8 = crtc_read(CURSOR_START_REGISTER);
+        let end_register:   u8 = crtc_read(CURSOR_END_REGISTER);
+        crtc_write(CURSOR_START_REGISTER, (start_register & 0xe0) | start);
+        crtc_write(CURSOR_END_REGISTER,   (end_register & 0xe0) | end);
     }
 
-    /// Adds a new line to the buffer, scrolling the text up by one.
+    /// Moves the hardware cursor to where the next character will be written.
+    fn sync_cursor(&self) -> () {
+        let row: usize = self.row_position;
+        let col: usize = self.column_position.min(BUFFER_WIDTH - 1);
+        set_cursor_location((row * BUFFER_WIDTH + col) as u16);
+    }
+
+    /// Moves the cursor to the start of the next row, scrolling the text up by one if the cursor is
+    /// already on the bottom row. The hardware cursor is left for the caller to synchronize.
     fn new_line(&mut self) -> () {
-        for row in 1..BUFFER_HEIGHT {
-            for col in 0..BUFFER_WIDTH {
-                    let char: VGAChar = self.buffer.chars[row][col].read();
-                    self.buffer.chars[row - 1][col].write(char);
+        if self.row_position < BUFFER_HEIGHT - 1 {
+            self.row_position += 1;
+        } else {
+            self.shift_cells(0, 0, BUFFER_HEIGHT, BUFFER_WIDTH, 1);
+            self.wrapped_rows.rotate_left(1);
+        }
+        self.wrapped_rows[self.row_position] = false;
+        self.column_position                 = 0;
+    }
+
+    /// Erases the character before the cursor and moves the cursor back onto it. At the start of a
+    /// row which was wrapped onto, this steps back onto the last column of the row above; at the
+    /// start of any other row, nothing happens.
+    fn backspace(&mut self) -> () {
+        let row: usize = self.row_position;
+        let col: usize = self.column_position.min(BUFFER_WIDTH);
+        let (row, col): (usize, usize) = match (row, col) {
+            (0, 0)                                  => return,
+            (_, 0) if self.wrapped_rows[row]        => {
+                self.wrapped_rows[row] = false;
+                (row - 1, BUFFER_WIDTH - 1)
+            },
+            (_, 0)                                  => return,
+            (_, _)                                  => (row, col - 1)
+        };
+
+        self.fill_cells(row, col, 1, 1, b' ', self.colour_desc);
+        self.row_position    = row;
+        self.column_position = col;
+    }
+
+    /// Fills a rectangular region with a character, leaving the wrapped rows untouched.
+    fn fill_cells(&mut self, top: usize, left: usize, height: usize, width: usize, char: u8, colour: VGAColourDesc) -> () {
+        let cell: VGAChar = VGAChar {
+            char,
+            desc: colour
+        };
+        for row in top..top + height {
+            for col in left..left + width {
+                self.buffer.chars[row][col].write(cell);
             }
         }
-        self.clear_row(BUFFER_HEIGHT - 1);
-        self.column_position = 0;
+    }
+
+    /// Shifts the contents of a rectangular region up by the given amount of lines, or down if the
+    /// amount is negative, leaving the wrapped rows untouched.
+    fn shift_cells(&mut self, top: usize, left: usize, height: usize, width: usize, lines: isize) -> () {
+        let distance: usize         = lines.unsigned_abs().min(height);
+        let colour:   VGAColourDesc = self.colour_desc;
+        if lines >= 0 {
+            for row in top..top + height - distance {
+                self.copy_row_span(row + distance, row, left, width);
+            }
+            self.fill_cells(top + height - distance, left, distance, width, b' ', colour);
+        } else {
+            for row in (top + distance..top + height).rev() {
+                self.copy_row_span(row - distance, row, left, width);
+            }
+            self.fill_cells(top, left, distance, width, b' ', colour);
+        }
+    }
+
+    /// Copies a span of cells from one row to another.
+    fn copy_row_span(&mut self, from: usize, to: usize, left: usize, width: usize) -> () {
+        for col in left..left + width {
+            let char: VGAChar = self.buffer.chars[from][col].read();
+            self.buffer.chars[to][col].write(char);
+        }
     }
 
     /// Clears a row on the buffer.
@@ -267,21 +609,255 @@ impl VGADriver {
     }
 }
 
+/// Checks that a rectangular region fits within the buffer.
+/// # Panics
+/// Panics if the region does not fit within the buffer.
+fn assert_region(top: usize, left: usize, height: usize, width: usize) -> () {
+    assert!(
+        top + height <= BUFFER_HEIGHT && left + width <= BUFFER_WIDTH,
+        "Region at ({}, {}) of {}x{} does not fit within the VGA buffer", top, left, width, height
+    );
+}
+
 impl fmt::Write for VGADriver {
 
-    /// Writes a whole string onto the VGA buffer.
+    /// Writes a whole string onto the VGA buffer, interpreting any ANSI escape sequences within it
+    /// and translating its characters onto code page 437.
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for byte in s.bytes() {
-            match byte {
-                0x20..=0x7e | b'\n' => self.write_byte(byte),   // Printable and supported VGA character.
-                _                   => self.write_byte(0xfe)    // Unsupported character outside of the VGA range.
+        for char in s.chars() {
+            let byte: u8 = if char.is_ascii() { char as u8 } else { 0xff };    // Not ASCII, so not part of any escape.
+            match (self.escape, byte) {
+                (EscapeState::Ground, ESCAPE) => self.escape = EscapeState::Escape,
+                (EscapeState::Ground, b'\n' | b'\r' | b'\t' | BACKSPACE | FORM_FEED) => self.put_byte(byte),
+                (EscapeState::Ground, _)      => self.draw_byte(cp437::encode_or_unmappable(char)),
+                (_, _)                        => self.process_escape(byte)
             }
         }
+        self.sync_cursor();
         Ok(())
     }
 }
 
 
+/*
+ * ANSI Escape
+ *      Sequences
+ */
+
+
+/// The progress through an escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EscapeState {
+
+    /// Not within an escape sequence.
+    Ground,
+
+    /// An escape character has been received.
+    Escape,
+
+    /// A control sequence introducer (`ESC [`) has been received, followed by the given
+    /// parameters. `private` marks a sequence starting with `?`.
+    Csi {
+        params:  [u16; MAX_CSI_PARAMS],
+        len:     usize,
+        private: bool
+    }
+}
+
+impl VGADriver {
+
+    /// Advances through an escape sequence by one byte, acting upon the sequence once it is
+    /// complete. Unsupported sequences are consumed and ignored.
+    /// # Note
+    /// As on a VT100, control characters within a sequence are acted upon without interrupting it,
+    /// whilst another escape character abandons the sequence and begins a new one.
+    fn process_escape(&mut self, byte: u8) -> () {
+        match (self.escape, byte) {
+            (_, ESCAPE)                                        => self.escape = EscapeState::Escape,
+            (_, CANCEL | SUBSTITUTE)                           => self.escape = EscapeState::Ground,
+            (_, b'\n' | b'\r' | b'\t' | BACKSPACE | FORM_FEED) => self.put_byte(byte),
+            (_, 0x00..=0x1f)                                   => (),    // Other control characters are ignored.
+            (EscapeState::Escape, b'[') => self.escape = EscapeState::Csi { params: [0; MAX_CSI_PARAMS], len: 0, private: false },
+            (EscapeState::Escape, b'7') => {
+                self.save_cursor();
+                self.escape = EscapeState::Ground;
+            },
+            (EscapeState::Escape, b'8') => {
+                self.restore_cursor();
+                self.escape = EscapeState::Ground;
+            },
+            (EscapeState::Csi { params, len, .. }, b'?') if len == 0 => self.escape = EscapeState::Csi { params, len, private: true },
+            (EscapeState::Csi { mut params, mut len, private }, b'0'..=b'9') => {
+                len = len.max(1);
+                if len <= MAX_CSI_PARAMS {
+                    params[len - 1] = params[len - 1].saturating_mul(10).saturating_add((byte - b'0') as u16);
+                }
+                self.escape = EscapeState::Csi { params, len, private };
+            },
+            (EscapeState::Csi { params, len, private }, b';') => self.escape = EscapeState::Csi { params, len: len.max(1) + 1, private },
+            (EscapeState::Csi { params, len, private }, 0x40..=0x7e) => {
+                self.escape = EscapeState::Ground;
+                self.execute_csi(byte, &params[..len.min(MAX_CSI_PARAMS)], private);
+            },
+            (EscapeState::Csi { .. }, _) => (),    // Intermediate bytes are accepted, but ignored.
+            (_, _)                       => self.escape = EscapeState::Ground
+        }
+    }
+
+    /// Acts upon a complete control sequence.
+    fn execute_csi(&mut self, command: u8, params: &[u16], private: bool) -> () {
+        
+        /// Gets a parameter, substituting the default if it is missing or zero.
+        fn param(params: &[u16], index: usize, default: u16) -> usize {
+            match params.get(index) {
+                Some(0) | None => default as usize,
+                Some(value)    => *value as usize
+            }
+        }
+
+        let (row, col): (usize, usize) = (self.row_position, self.column_position.min(BUFFER_WIDTH - 1));
+        match (private, command) {
+            (true,  b'h') if params == [25] => self.show_cursor(),
+            (true,  b'l') if params == [25] => self.hide_cursor(),
+            (false, b'A') => self.set_position(row.saturating_sub(param(params, 0, 1)), col),
+            (false, b'B') => self.set_position((row + param(params, 0, 1)).min(BUFFER_HEIGHT - 1), col),
+            (false, b'C') => self.set_position(row, (col + param(params, 0, 1)).min(BUFFER_WIDTH - 1)),
+            (false, b'D') => self.set_position(row, col.saturating_sub(param(params, 0, 1))),
+            (false, b'G') => self.set_position(row, param(params, 0, 1).min(BUFFER_WIDTH) - 1),
+            (false, b'H') | (false, b'f') => {
+                self.set_position(param(params, 0, 1).min(BUFFER_HEIGHT) - 1, param(params, 1, 1).min(BUFFER_WIDTH) - 1);
+            },
+            (false, b'J') => self.erase_in_display(params.first().copied().unwrap_or(0)),
+            (false, b'K') => self.erase_in_line(params.first().copied().unwrap_or(0)),
+            (false, b'm') => self.select_graphic_rendition(params),
+            (false, b's') => self.save_cursor(),
+            (false, b'u') => self.restore_cursor(),
+            _             => ()
+        }
+    }
+
+    /// Erases part of the screen: from the cursor to the end (0), from the start to the cursor (1),
+    /// or all of it (2 or 3). The cursor does not move.
+    fn erase_in_display(&mut self, mode: u16) -> () {
+        let (row, col): (usize, usize) = (self.row_position, self.column_position.min(BUFFER_WIDTH - 1));
+        let colour:     VGAColourDesc  = self.colour_desc;
+        match mode {
+            0 => {
+                self.fill_region(row, col, 1, BUFFER_WIDTH - col, b' ', colour);
+                self.fill_region(row + 1, 0, BUFFER_HEIGHT - row - 1, BUFFER_WIDTH, b' ', colour);
+            },
+            1 => {
+                self.fill_region(0, 0, row, BUFFER_WIDTH, b' ', colour);
+                self.fill_region(row, 0, 1, col + 1, b' ', colour);
+            },
+            2 | 3 => self.fill_region(0, 0, BUFFER_HEIGHT, BUFFER_WIDTH, b' ', colour),
+            _     => ()
+        }
+    }
+
+    /// Erases part of the cursor's row: from the cursor to the end (0), from the start to the cursor
+    /// (1), or all of it (2). The cursor does not move.
+    fn erase_in_line(&mut self, mode: u16) -> () {
+        let (row, col): (usize, usize) = (self.row_position, self.column_position.min(BUFFER_WIDTH - 1));
+        let colour:     VGAColourDesc  = self.colour_desc;
+        match mode {
+            0 => self.fill_region(row, col, 1, BUFFER_WIDTH - col, b' ', colour),
+            1 => self.fill_region(row, 0, 1, col + 1, b' ', colour),
+            2 => self.fill_region(row, 0, 1, BUFFER_WIDTH, b' ', colour),
+            _ => ()
+        }
+    }
+
+    /// Applies SGR parameters to the colour that characters are written in. The bright background
+    /// colours fall back to their normal variants, as the background's fourth bit controls blinking.
+    fn select_graphic_rendition(&mut self, params: &[u16]) -> () {
+        let params: &[u16] = if params.is_empty() { &[0] } else { params };
+        for param in params {
+            let colour: VGAColourDesc = self.colour_desc;
+            self.colour_desc = match *param {
+                0          => {
+                    self.bold = false;
+                    self.default_colour
+                },
+                1          => {
+                    self.bold = true;
+                    colour.with_foreground(VGAColourFull::from_raw(colour.foreground().to_raw().0, true))
+                },
+                22         => {
+                    self.bold = false;
+                    colour.with_foreground(VGAColourFull::from_raw(colour.foreground().to_raw().0, false))
+                },
+                5          => colour.with_blink(true),
+                25         => colour.with_blink(false),
+                30..=37    => colour.with_foreground(self.ansi_foreground(*param - 30, self.bold)),
+                39         => colour.with_foreground(self.default_colour.foreground()),
+                40..=47    => colour.with_background(VGAColour::from_ansi(*param - 40).unwrap()),
+                49         => colour.with_background(self.default_colour.background()),
+                90..=97    => colour.with_foreground(self.ansi_foreground(*param - 90, true)),
+                100..=107  => colour.with_background(VGAColour::from_ansi(*param - 100).unwrap()),
+                _          => colour
+            };
+        }
+    }
+
+    /// Converts an ANSI colour index into a foreground colour.
+    fn ansi_foreground(&self, index: u16, bright: bool) -> VGAColourFull {
+        VGAColourFull::from_raw(VGAColour::from_ansi(index).unwrap(), bright)
+    }
+
+    /// Saves the cursor's position and colour.
+    fn save_cursor(&mut self) -> () {
+        self.saved_cursor = (self.row_position, self.column_position, self.colour_desc);
+    }
+
+    /// Restores the cursor's position and colour from when it was last saved.
+    fn restore_cursor(&mut self) -> () {
+        let (row, col, colour): (usize, usize, VGAColourDesc) = self.saved_cursor;
+        self.colour_desc = colour;
+        self.set_position(row, col.min(BUFFER_WIDTH - 1));
+    }
+}
+
+
+/*
+ * Hardware
+ *      Cursor
+ */
+
+
+/// Reads a register of the CRT controller.
+fn crtc_read(register: u8) -> u8 {
+    let mut index: Port<u8> = Port::new(CRTC_INDEX_PORT);
+    let mut data:  Port<u8> = Port::new(CRTC_DATA_PORT);
+    unsafe {
+        index.write(register);
+        data.read()
+    }
+}
+
+/// Writes a register of the CRT controller.
+fn crtc_write(register: u8, value: u8) -> () {
+    let mut index: Port<u8> = Port::new(CRTC_INDEX_PORT);
+    let mut data:  Port<u8> = Port::new(CRTC_DATA_PORT);
+    unsafe {
+        index.write(register);
+        data.write(value);
+    }
+}
+
+/// Moves the hardware cursor to the given cell, counted row by row from the top left.
+fn set_cursor_location(location: u16) -> () {
+    crtc_write(CURSOR_LOCATION_HIGH_REGISTER, (location >> 8) as u8);
+    crtc_write(CURSOR_LOCATION_LOW_REGISTER,  location as u8);
+}
+
+/// Reads the hardware cursor's location back from the CRT controller, as a row and column.
+pub fn cursor_position() -> (usize, usize) {
+    let location: usize = (crtc_read(CURSOR_LOCATION_HIGH_REGISTER) as usize) << 8 | crtc_read(CURSOR_LOCATION_LOW_REGISTER) as usize;
+    (location / BUFFER_WIDTH, location % BUFFER_WIDTH)
+}
+
+
 /*
  * Print Macro
  *      Support
@@ -305,6 +881,22 @@ pub fn _print(args: fmt::Arguments) -> () {
     WRITER.lock().write_fmt(args).unwrap();
 }
 
+/// Prints to the VGA buffer only if it is not already being written to, skipping the output
+/// otherwise. This is for contexts which may have interrupted the writer and later resume it, such
+/// as exception handlers, where waiting for the lock would deadlock.
+pub fn try_print(args: fmt::Arguments) -> () {
+    if let Some(mut writer) = WRITER.try_lock() {
+        let _ = writer.write_fmt(args);
+    }
+}
+
+/// Prints to the VGA buffer even if it is already being written to.
+/// # Safety
+/// Whatever was writing to the buffer must never resume, such as when printing from a panic.
+pub unsafe fn force_print(args: fmt::Arguments) -> () {
+    let _ = WRITER.force_lock().write_fmt(args);
+}
+
 
 /*
  * VGA Driver
@@ -312,6 +904,59 @@ pub fn _print(args: fmt::Arguments) -> () {
  */
 
 
+/// A copy of a range of rows, along with the cursor and colour state, which a test takes before
+/// drawing onto the screen so that it may put everything back once it is done.
+#[cfg(test)]
+struct ScreenSnapshot {
+    rows:         core::ops::Range<usize>,
+    cells:        [[VGAChar; BUFFER_WIDTH]; BUFFER_HEIGHT],
+    wrapped_rows: [bool; BUFFER_HEIGHT],
+    position:     (usize, usize),
+    colour:       VGAColourDesc,
+    bold:         bool,
+    saved_cursor: (usize, usize, VGAColourDesc)
+}
+
+#[cfg(test)]
+impl VGADriver {
+
+    /// Takes a snapshot of the given rows, along with the cursor and colour state.
+    fn snapshot(&self, rows: core::ops::Range<usize>) -> ScreenSnapshot {
+        let blank:     VGAChar                                  = VGAChar { char: b' ', desc: self.colour_desc };
+        let mut cells: [[VGAChar; BUFFER_WIDTH]; BUFFER_HEIGHT] = [[blank; BUFFER_WIDTH]; BUFFER_HEIGHT];
+        for row in rows.clone() {
+            for (cell, char) in cells[row].iter_mut().zip(&self.buffer.chars[row]) {
+                *cell = char.read();
+            }
+        }
+        
+        ScreenSnapshot {
+            rows,
+            cells,
+            wrapped_rows: self.wrapped_rows,
+            position:     self.position(),
+            colour:       self.colour_desc,
+            bold:         self.bold,
+            saved_cursor: self.saved_cursor
+        }
+    }
+
+    /// Restores the rows, cursor and colour state captured by a snapshot.
+    fn restore(&mut self, snapshot: ScreenSnapshot) -> () {
+        for row in snapshot.rows {
+            for (char, cell) in self.buffer.chars[row].iter_mut().zip(&snapshot.cells[row]) {
+                char.write(*cell);
+            }
+            self.wrapped_rows[row] = snapshot.wrapped_rows[row];
+        }
+        
+        self.colour_desc  = snapshot.colour;
+        self.bold         = snapshot.bold;
+        self.saved_cursor = snapshot.saved_cursor;
+        self.set_position(snapshot.position.0, snapshot.position.1);
+    }
+}
+
 #[test_case]
 fn test_println_simple() -> () {
     println!("Hello, World!");
@@ -333,3 +978,249 @@ fn test_println_output() -> () {
         assert_eq!(char::from(vga_char.char), c);
     }
 }
+
+#[test_case]
+fn test_cursor_follows_output() -> () {
+    println!();
+    print!("cursor");
+    assert_eq!(cursor_position(), (BUFFER_HEIGHT - 1, 6));
+    
+    println!();
+    assert_eq!(cursor_position(), (BUFFER_HEIGHT - 1, 0));
+}
+
+#[test_case]
+fn test_cursor_visibility_and_shape() -> () {
+    let mut writer: IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     start:  u8                       = crtc_read(CURSOR_START_REGISTER);
+    let     end:    u8                       = crtc_read(CURSOR_END_REGISTER);
+    writer.hide_cursor();
+    assert_ne!(crtc_read(CURSOR_START_REGISTER) & CURSOR_DISABLE, 0);
+    
+    writer.show_cursor();
+    writer.set_cursor_shape(14, 15);
+    assert_eq!(crtc_read(CURSOR_START_REGISTER) & (CURSOR_DISABLE | 0x1f), 14);
+    assert_eq!(crtc_read(CURSOR_END_REGISTER) & 0x1f, 15);
+    crtc_write(CURSOR_START_REGISTER, start);
+    crtc_write(CURSOR_END_REGISTER, end);
+}
+
+#[test_case]
+fn test_set_position() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(3..4);
+    
+    writer.set_position(3, 10);
+    writer.write_str("here").unwrap();
+    assert_eq!(writer.position(), (3, 14));
+    assert_eq!(cursor_position(), (3, 14));
+    for (i, c) in "here".bytes().enumerate() {
+        assert_eq!(writer.buffer.chars[3][10 + i].read().char, c);
+    }
+    
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_write_at_with_colour() -> () {
+    let     colour:   VGAColourDesc            = VGAColourDesc::new(VGAColourFull::Yellow, VGAColour::Blue, false);
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(0..1);
+    
+    writer.write_at(0, BUFFER_WIDTH - 3, "status", colour);
+    assert_eq!(writer.position(), snapshot.position);
+    for (i, c) in "sta".bytes().enumerate() {
+        let cell: VGAChar = writer.buffer.chars[0][BUFFER_WIDTH - 3 + i].read();
+        assert_eq!(cell, VGAChar { char: c, desc: colour });
+    }
+    assert_eq!(writer.colour_at(0, BUFFER_WIDTH - 1), colour);
+    assert_eq!(writer.char_at(0, BUFFER_WIDTH - 1), b'a');
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_fill_and_scroll_region() -> () {
+    let     colour:   VGAColourDesc            = VGAColourDesc::new(VGAColourFull::Black, VGAColour::LightGrey, false);
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(2..5);
+    
+    writer.fill_region(2, 4, 3, 5, b'#', colour);
+    writer.write_at(2, 4, "abcde", colour);
+    writer.write_at(4, 4, "vwxyz", colour);
+    
+    // Scrolling up moves the bottom line to the top of the region and blanks the rest.
+    writer.scroll_region(2, 4, 3, 5, 2);
+    assert_eq!(writer.char_at(2, 4), b'v');
+    assert_eq!(writer.char_at(2, 8), b'z');
+    assert_eq!(writer.char_at(3, 4), b' ');
+    assert_eq!(writer.char_at(4, 8), b' ');
+    
+    // Scrolling down moves it back, without touching anything outside of the region.
+    writer.scroll_region(2, 4, 3, 5, -2);
+    assert_eq!(writer.char_at(4, 4), b'v');
+    assert_eq!(writer.char_at(2, 4), b' ');
+    assert_eq!(writer.colour_at(4, 4), colour);
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_ansi_colours() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(5..6);
+    let     default:  VGAColourDesc            = writer.colour();
+    
+    writer.set_position(5, 0);
+    writer.write_str("\x1b[31;44mR\x1b[1;32mG\x1b[0mD\x1b[95mP").unwrap();
+    assert_eq!(writer.colour_at(5, 0), default.with_foreground(VGAColourFull::Red).with_background(VGAColour::Blue));
+    assert_eq!(writer.colour_at(5, 1), default.with_foreground(VGAColourFull::LightGreen).with_background(VGAColour::Blue));
+    assert_eq!(writer.colour_at(5, 2), default);
+    assert_eq!(writer.colour_at(5, 3).foreground(), VGAColourFull::Pink);
+    assert_eq!(writer.char_at(5, 0), b'R');
+    assert_eq!(writer.char_at(5, 3), b'P');
+    
+    writer.write_str("\x1b[m").unwrap();
+    assert_eq!(writer.colour(), default);
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_ansi_cursor_movement() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(0..0);    // Only the cursor moves.
+    
+    writer.write_str("\x1b[5;10H").unwrap();
+    assert_eq!(writer.position(), (4, 9));
+    writer.write_str("\x1b[2A\x1b[3C").unwrap();
+    assert_eq!(writer.position(), (2, 12));
+    writer.write_str("\x1b[B\x1b[100D").unwrap();
+    assert_eq!(writer.position(), (3, 0));
+    
+    writer.write_str("\x1b[s\x1b[20;1H\x1b[u").unwrap();
+    assert_eq!(writer.position(), (3, 0));
+    writer.write_str("\x1b7\x1b[H\x1b8").unwrap();
+    assert_eq!(writer.position(), (3, 0));
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_ansi_erase() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(6..7);
+    let     colour:   VGAColourDesc            = writer.colour();
+    
+    writer.write_at(6, 0, "erase this line", colour);
+    writer.set_position(6, 6);
+    writer.write_str("\x1b[K").unwrap();
+    assert_eq!(writer.char_at(6, 5), b' ');
+    assert_eq!(writer.char_at(6, 4), b'e');
+    assert_eq!(writer.char_at(6, 6), b' ');
+    
+    writer.write_str("\x1b[1K").unwrap();
+    assert_eq!(writer.char_at(6, 0), b' ');
+    
+    // Unsupported sequences are swallowed rather than printed.
+    writer.write_str("\x1b[?1049hA").unwrap();
+    assert_eq!(writer.char_at(6, 6), b'A');
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_control_characters() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(7..9);
+    
+    writer.set_position(7, 0);
+    writer.write_str("abc\rX").unwrap();
+    assert_eq!(writer.char_at(7, 0), b'X');
+    assert_eq!(writer.char_at(7, 1), b'b');
+    
+    writer.write_str("\tT\tU").unwrap();
+    assert_eq!(writer.char_at(7, 8), b'T');
+    assert_eq!(writer.char_at(7, 16), b'U');
+    
+    writer.write_str("\x08").unwrap();
+    assert_eq!(writer.char_at(7, 16), b' ');
+    assert_eq!(writer.position(), (7, 16));
+    
+    // A backspace at the start of a row which wasn't wrapped onto stays put.
+    writer.write_str("\n\x08").unwrap();
+    assert_eq!(writer.position(), (8, 0));
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_backspace_across_wrap() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(9..11);
+    
+    writer.set_position(9, BUFFER_WIDTH - 1);
+    writer.write_str("yz\x08").unwrap();
+    assert_eq!(writer.position(), (10, 0));
+    assert_eq!(writer.char_at(10, 0), b' ');
+    
+    writer.write_str("\x08").unwrap();
+    assert_eq!(writer.position(), (9, BUFFER_WIDTH - 1));
+    assert_eq!(writer.char_at(9, BUFFER_WIDTH - 1), b' ');
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_unicode_output() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(11..12);
+    
+    writer.set_position(11, 0);
+    writer.write_str("┌─é▒α→あ").unwrap();
+    assert_eq!(writer.char_at(11, 0), 0xda);
+    assert_eq!(writer.char_at(11, 1), 0xc4);
+    assert_eq!(writer.char_at(11, 2), 0x82);
+    assert_eq!(writer.char_at(11, 3), 0xb1);
+    assert_eq!(writer.char_at(11, 4), 0xe0);
+    assert_eq!(writer.char_at(11, 5), 0x1a);
+    assert_eq!(writer.char_at(11, 6), cp437::UNMAPPABLE);
+    assert_eq!(writer.position(), (11, 7));
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_restore_filled_row_position() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(12..14);
+    
+    writer.set_position(12, BUFFER_WIDTH - 1);
+    writer.write_str("x").unwrap();
+    assert_eq!(writer.position(), (12, BUFFER_WIDTH));
+    
+    let (filled_row, filled_col): (usize, usize) = writer.position();
+    writer.set_position(0, 0);
+    writer.set_position(filled_row, filled_col);
+    writer.write_str("y").unwrap();
+    assert_eq!(writer.position(), (13, 1));
+    
+    // Scrolling the wrapped row away forgets the wrap, so a backspace stays on its row.
+    writer.scroll_region(13, 0, 1, BUFFER_WIDTH, 1);
+    writer.write_str("\x08\x08").unwrap();
+    assert_eq!(writer.position(), (13, 0));
+    writer.restore(snapshot);
+}
+
+#[test_case]
+fn test_ansi_interrupted_sequences() -> () {
+    let mut writer:   IrqMutexGuard<VGADriver> = WRITER.lock();
+    let     snapshot: ScreenSnapshot           = writer.snapshot(14..15);
+    let     default:  VGAColourDesc            = writer.colour();
+    
+    // A second escape begins a new sequence rather than being swallowed.
+    writer.set_position(14, 0);
+    writer.write_str("\x1b\x1b[31mR\x1b[0m").unwrap();
+    assert_eq!(writer.char_at(14, 0), b'R');
+    assert_eq!(writer.colour_at(14, 0).foreground(), VGAColourFull::Red);
+    
+    // Control characters are acted upon in the middle of a sequence.
+    writer.write_str("ab\x1b[2\rC").unwrap();
+    assert_eq!(writer.position(), (14, 2));
+    writer.write_str("\x1b[3\x18X").unwrap();
+    assert_eq!(writer.char_at(14, 2), b'X');
+    assert_eq!(writer.colour(), default);
+    writer.restore(snapshot);
+}
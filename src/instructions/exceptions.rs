@@ -0,0 +1,611 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$  /$$$$$$$  /$$   /$$       /$$$$$$$$                                           /$$     /$$                              
+//  /$$__  $$| $$__  $$| $$  | $$      | $$_____/                                          | $$    |__/                              
+// | $$  \__/| $$  \ $$| $$  | $$      | $$       /$$   /$$  /$$$$$$$  /$$$$$$   /$$$$$$  /$$$$$$   /$$  /$$$$$$  /$$$$$$$   /$$$$$$$
+// | $$      | $$$$$$$/| $$  | $$      | $$$$$   |  $$ /$$/ /$$_____/ /$$__  $$ /$$__  $$|_  $$_/  | $$ /$$__  $$| $$__  $$ /$$_____/
+// | $$      | $$____/ | $$  | $$      | $$__/    \  $$$$/ | $$      | $$$$$$$$| $$  \ $$  | $$    | $$| $$  \ $$| $$  \ $$|  $$$$$$ 
+// | $$    $$| $$      | $$  | $$      | $$        >$$  $$ | $$      | $$_____/| $$  | $$  | $$ /$$| $$| $$  | $$| $$  | $$ \____  $$
+// |  $$$$$$/| $$      |  $$$$$$/      | $$$$$$$$ /$$/\  $$|  $$$$$$$|  $$$$$$$| $$$$$$$/  |  $$$$/| $$|  $$$$$$/| $$  | $$ /$$$$$$$/
+//  \______/ |__/       \______/       |________/|__/  \__/ \_______/ \_______/| $$____/    \___/  |__/ \______/ |__/  |__/|_______/ 
+//                                                                             | $$                                                  
+//                                                                             | $$                                                  
+//                                                                             |__/                                                  
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Handles every architectural CPU exception (vectors 0 through 31), reporting the faulting context
+//! and routing each exception through a pluggable policy that decides whether execution may resume.
+//!
+
+use core::fmt;
+use core::sync::atomic::{ AtomicUsize, Ordering };
+
+use x86_64::{ PrivilegeLevel, VirtAddr };
+use x86_64::structures::idt::{ InterruptDescriptorTable as InterruptDescTable, InterruptStackFrame, InterruptStackFrameValue, PageFaultErrorCode, SelectorErrorCode };
+use x86_64::registers::control::Cr2;
+
+use crate::drivers::{ vga_text, serial };
+#[cfg(test)]
+use crate::memory::stack::KernelStack;
+use super::gdt;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The policy that exceptions are routed through, stored as a raw function pointer. A value of zero
+/// marks that the default policy is used.
+static EXCEPTION_POLICY: AtomicUsize = AtomicUsize::new(0);
+
+/// The resolver that page faults are handed off to, stored as a raw function pointer. A value of
+/// zero marks that no resolver is installed.
+static PAGE_FAULT_RESOLVER: AtomicUsize = AtomicUsize::new(0);
+
+
+/*
+ * Exception
+ *      Enum
+ */
+
+
+/// Encapsulates every architecturally defined CPU exception, along with its interrupt vector.
+/// # Note
+/// Vectors 15, 22 through 27 and 31 are reserved by the architecture and are never raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Exception {
+    DivideError               = 0,
+    Debug                     = 1,
+    NonMaskableInterrupt      = 2,
+    Breakpoint                = 3,
+    Overflow                  = 4,
+    BoundRangeExceeded        = 5,
+    InvalidOpcode             = 6,
+    DeviceNotAvailable        = 7,
+    DoubleFault               = 8,
+    CoprocessorSegmentOverrun = 9,
+    InvalidTss                = 10,
+    SegmentNotPresent         = 11,
+    StackSegmentFault         = 12,
+    GeneralProtectionFault    = 13,
+    PageFault                 = 14,
+    X87FloatingPoint          = 16,
+    AlignmentCheck            = 17,
+    MachineCheck              = 18,
+    SimdFloatingPoint         = 19,
+    Virtualization            = 20,
+    ControlProtection         = 21,
+    HypervisorInjection       = 28,
+    VmmCommunication          = 29,
+    Security                  = 30
+}
+
+impl Exception {
+
+    /// Every architecturally defined exception, in the order of their vectors.
+    pub const ALL: [Exception; 24] = [
+        Self::DivideError,       Self::Debug,                     Self::NonMaskableInterrupt, Self::Breakpoint,
+        Self::Overflow,          Self::BoundRangeExceeded,        Self::InvalidOpcode,        Self::DeviceNotAvailable,
+        Self::DoubleFault,       Self::CoprocessorSegmentOverrun, Self::InvalidTss,           Self::SegmentNotPresent,
+        Self::StackSegmentFault, Self::GeneralProtectionFault,    Self::PageFault,            Self::X87FloatingPoint,
+        Self::AlignmentCheck,    Self::MachineCheck,              Self::SimdFloatingPoint,    Self::Virtualization,
+        Self::ControlProtection, Self::HypervisorInjection,       Self::VmmCommunication,     Self::Security
+    ];
+
+    /// Gets the interrupt vector of this exception.
+    pub fn vector(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets the human-readable name of this exception.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DivideError               => "DIVIDE ERROR",
+            Self::Debug                     => "DEBUG",
+            Self::NonMaskableInterrupt      => "NON-MASKABLE INTERRUPT",
+            Self::Breakpoint                => "BREAKPOINT",
+            Self::Overflow                  => "OVERFLOW",
+            Self::BoundRangeExceeded        => "BOUND RANGE EXCEEDED",
+            Self::InvalidOpcode             => "INVALID OPCODE",
+            Self::DeviceNotAvailable        => "DEVICE NOT AVAILABLE",
+            Self::DoubleFault               => "DOUBLE FAULT",
+            Self::CoprocessorSegmentOverrun => "COPROCESSOR SEGMENT OVERRUN",
+            Self::InvalidTss                => "INVALID TSS",
+            Self::SegmentNotPresent         => "SEGMENT NOT PRESENT",
+            Self::StackSegmentFault         => "STACK-SEGMENT FAULT",
+            Self::GeneralProtectionFault    => "GENERAL PROTECTION FAULT",
+            Self::PageFault                 => "PAGE FAULT",
+            Self::X87FloatingPoint          => "X87 FLOATING-POINT EXCEPTION",
+            Self::AlignmentCheck            => "ALIGNMENT CHECK",
+            Self::MachineCheck              => "MACHINE CHECK",
+            Self::SimdFloatingPoint         => "SIMD FLOATING-POINT EXCEPTION",
+            Self::Virtualization            => "VIRTUALIZATION EXCEPTION",
+            Self::ControlProtection         => "CONTROL PROTECTION EXCEPTION",
+            Self::HypervisorInjection       => "HYPERVISOR INJECTION EXCEPTION",
+            Self::VmmCommunication          => "VMM COMMUNICATION EXCEPTION",
+            Self::Security                  => "SECURITY EXCEPTION"
+        }
+    }
+
+    /// Gets the mnemonic of this exception, such as `#GP`.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Self::DivideError               => "#DE",
+            Self::Debug                     => "#DB",
+            Self::NonMaskableInterrupt      => "NMI",
+            Self::Breakpoint                => "#BP",
+            Self::Overflow                  => "#OF",
+            Self::BoundRangeExceeded        => "#BR",
+            Self::InvalidOpcode             => "#UD",
+            Self::DeviceNotAvailable        => "#NM",
+            Self::DoubleFault               => "#DF",
+            Self::CoprocessorSegmentOverrun => "#CSO",
+            Self::InvalidTss                => "#TS",
+            Self::SegmentNotPresent         => "#NP",
+            Self::StackSegmentFault         => "#SS",
+            Self::GeneralProtectionFault    => "#GP",
+            Self::PageFault                 => "#PF",
+            Self::X87FloatingPoint          => "#MF",
+            Self::AlignmentCheck            => "#AC",
+            Self::MachineCheck              => "#MC",
+            Self::SimdFloatingPoint         => "#XM",
+            Self::Virtualization            => "#VE",
+            Self::ControlProtection         => "#CP",
+            Self::HypervisorInjection       => "#HV",
+            Self::VmmCommunication          => "#VC",
+            Self::Security                  => "#SX"
+        }
+    }
+
+    /// Whether the error code pushed by this exception references a segment selector.
+    pub fn has_selector_error_code(self) -> bool {
+        matches!(self, Self::InvalidTss | Self::SegmentNotPresent | Self::StackSegmentFault | Self::GeneralProtectionFault)
+    }
+
+    /// Whether execution can never resume after this exception, regardless of policy.
+    pub fn is_diverging(self) -> bool {
+        matches!(self, Self::DoubleFault | Self::MachineCheck)
+    }
+
+    /// The action taken for this exception if no policy has been installed. Traps which leave the
+    /// faulting context intact resume, whilst every fault or abort panics.
+    pub fn default_action(self) -> ExceptionAction {
+        match self {
+            Self::Debug | Self::NonMaskableInterrupt | Self::Breakpoint | Self::Overflow => ExceptionAction::Resume,
+            _                                                                            => ExceptionAction::Panic
+        }
+    }
+}
+
+
+/*
+ * Exception Policy
+ *      Hook
+ */
+
+
+/// The action to be taken once an exception has been reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionAction {
+
+    /// Returns from the handler, resuming execution in the interrupted context.
+    /// # Note
+    /// Faults restart the faulting instruction, so resuming after a fault whose cause has not been
+    /// dealt with will simply raise it again.
+    Resume,
+
+    /// Panics with the exception's report.
+    Panic
+}
+
+/// A policy which decides the action to be taken for every reported exception.
+pub type ExceptionPolicy = fn(&ExceptionReport) -> ExceptionAction;
+
+/// Installs the policy that exceptions are routed through, or restores the default policy if `None`
+/// is given.
+pub fn set_exception_policy(policy: Option<ExceptionPolicy>) -> () {
+    EXCEPTION_POLICY.store(policy.map_or(0, |policy| policy as usize), Ordering::Release);
+}
+
+/// Gets the action to be taken for an exception from the installed policy, or from the default
+/// policy if none is installed.
+fn exception_action(report: &ExceptionReport) -> ExceptionAction {
+    match EXCEPTION_POLICY.load(Ordering::Acquire) {
+        0      => report.exception.default_action(),
+        policy => {
+            let policy: ExceptionPolicy = unsafe { core::mem::transmute::<usize, ExceptionPolicy>(policy) };
+            policy(report)
+        }
+    }
+}
+
+/// Describes a single CPU exception, along with the context it was raised in.
+#[derive(Clone, Copy)]
+pub struct ExceptionReport {
+
+    /// The exception that was raised.
+    pub exception: Exception,
+
+    /// The error code pushed by the CPU, if the exception pushes one.
+    pub error_code: Option<u64>,
+
+    /// The stack frame of the interrupted context.
+    pub stack_frame: InterruptStackFrameValue
+}
+
+impl ExceptionReport {
+
+    /// Decodes the error code as a segment selector error code, if the exception pushes one.
+    pub fn selector_error_code(&self) -> Option<SelectorErrorCode> {
+        if !self.exception.has_selector_error_code() {
+            return None;
+        }
+        self.error_code.map(SelectorErrorCode::new_truncate)
+    }
+
+    /// Whether the exception was raised by code running in user mode.
+    pub fn from_user_mode(&self) -> bool {
+        self.stack_frame.code_segment & 0b11 == PrivilegeLevel::Ring3 as u64
+    }
+}
+
+impl fmt::Display for ExceptionReport {
+
+    /// Formats the report into a multi-line diagnostic.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "EXCEPTION: {} ({}, vector {})", self.exception.name(), self.exception.mnemonic(), self.exception.vector())?;
+        if let Some(error_code) = self.error_code {
+            writeln!(f, "    Error Code:    {error_code:#x}")?;
+        }
+        if let Some(selector) = self.selector_error_code().filter(|selector| !selector.is_null()) {
+            writeln!(f, "    Selector:      {:?} index {:#x}", selector.descriptor_table(), selector.index())?;
+            writeln!(f, "    External:      {}", selector.external())?;
+        }
+        write!(f, "{:#?}", self.stack_frame)
+    }
+}
+
+/// Prints a report to both the VGA screen and the serial interface without waiting on either's
+/// lock, as the exception may have interrupted code that holds it. The VGA output is skipped if
+/// its lock is held, whilst the serial output is always written.
+fn print_report(report: &dyn fmt::Display) -> () {
+    vga_text::try_print(format_args!("{}\n", report));
+    unsafe { serial::force_print(format_args!("{}\n", report)) };
+}
+
+/// Routes an exception through the policy, reporting it to both the VGA screen and the serial
+/// interface before acting upon it.
+fn handle_exception(exception: Exception, error_code: Option<u64>, stack_frame: &InterruptStackFrame) -> () {
+    let report: ExceptionReport = ExceptionReport {
+        exception,
+        error_code,
+        stack_frame: **stack_frame
+    };
+    let action: ExceptionAction = exception_action(&report);
+
+    print_report(&report);
+    if action == ExceptionAction::Panic || exception.is_diverging() {
+        panic!("EXCEPTION: {}", exception.name());
+    }
+}
+
+
+/*
+ * Handler
+ *      Installation
+ */
+
+
+/// Installs a handler for every architecturally defined exception into the given IDT.
+pub(super) fn set_handlers(idt: &mut InterruptDescTable) -> () {
+    for exception in Exception::ALL {
+        set_handler(idt, exception);
+    }
+}
+
+/// Installs the handler for a single exception into the given IDT.
+pub(crate) fn set_handler(idt: &mut InterruptDescTable, exception: Exception) -> () {
+    match exception {
+        Exception::DivideError               => { idt.divide_error.set_handler_fn(divide_error_handler); },
+        Exception::Debug                     => { idt.debug.set_handler_fn(debug_handler); },
+        Exception::Breakpoint                => { idt.breakpoint.set_handler_fn(breakpoint_handler).set_privilege_level(PrivilegeLevel::Ring3); },    // Allow `int3` from user mode.
+        Exception::Overflow                  => { idt.overflow.set_handler_fn(overflow_handler); },
+        Exception::BoundRangeExceeded        => { idt.bound_range_exceeded.set_handler_fn(bound_range_exceeded_handler); },
+        Exception::InvalidOpcode             => { idt.invalid_opcode.set_handler_fn(invalid_opcode_handler); },
+        Exception::DeviceNotAvailable        => { idt.device_not_available.set_handler_fn(device_not_available_handler); },
+        Exception::CoprocessorSegmentOverrun => { idt[exception.vector() as usize].set_handler_fn(coprocessor_segment_overrun_handler); },
+        Exception::InvalidTss                => { idt.invalid_tss.set_handler_fn(invalid_tss_handler); },
+        Exception::SegmentNotPresent         => { idt.segment_not_present.set_handler_fn(segment_not_present_handler); },
+        Exception::StackSegmentFault         => { idt.stack_segment_fault.set_handler_fn(stack_segment_fault_handler); },
+        Exception::GeneralProtectionFault    => { idt.general_protection_fault.set_handler_fn(general_protection_fault_handler); },
+        Exception::X87FloatingPoint          => { idt.x87_floating_point.set_handler_fn(x87_floating_point_handler); },
+        Exception::AlignmentCheck            => { idt.alignment_check.set_handler_fn(alignment_check_handler); },
+        Exception::SimdFloatingPoint         => { idt.simd_floating_point.set_handler_fn(simd_floating_point_handler); },
+        Exception::Virtualization            => { idt.virtualization.set_handler_fn(virtualization_handler); },
+        Exception::ControlProtection         => { idt.cp_protection_exception.set_handler_fn(control_protection_handler); },
+        Exception::HypervisorInjection       => { idt.hv_injection_exception.set_handler_fn(hypervisor_injection_handler); },
+        Exception::VmmCommunication          => { idt.vmm_communication_exception.set_handler_fn(vmm_communication_handler); },
+        Exception::Security                  => { idt.security_exception.set_handler_fn(security_handler); },
+        
+        // These may be raised whilst the kernel stack is unusable, so they switch to their own stacks.
+        Exception::DoubleFault               => unsafe { idt.double_fault.set_handler_fn(double_fault_handler).set_stack_index(gdt::DOUBLE_FAULT_IST_INDEX); },
+        Exception::NonMaskableInterrupt      => unsafe { idt.non_maskable_interrupt.set_handler_fn(non_maskable_interrupt_handler).set_stack_index(gdt::NMI_IST_INDEX); },
+        Exception::MachineCheck              => unsafe { idt.machine_check.set_handler_fn(machine_check_handler).set_stack_index(gdt::MACHINE_CHECK_IST_INDEX); },
+        Exception::PageFault                 => unsafe { idt.page_fault.set_handler_fn(page_fault_handler).set_stack_index(gdt::PAGE_FAULT_IST_INDEX); }
+    }
+}
+
+
+/*
+ * Exception
+ *      Handlers
+ */
+
+
+/// Generates a handler for each of the given exceptions, which routes the exception through the
+/// policy.
+macro_rules! exception_handlers {
+    ($($name:ident => $exception:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Handles the `", stringify!($exception), "` exception.")]
+            pub extern "x86-interrupt" fn $name(stack_frame: InterruptStackFrame) {
+                handle_exception(Exception::$exception, None, &stack_frame);
+            }
+        )*
+    };
+}
+
+/// Generates a handler for each of the given exceptions which push an error code, which routes the
+/// exception through the policy.
+macro_rules! exception_handlers_with_error_code {
+    ($($name:ident => $exception:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("Handles the `", stringify!($exception), "` exception.")]
+            pub extern "x86-interrupt" fn $name(stack_frame: InterruptStackFrame, error_code: u64) {
+                handle_exception(Exception::$exception, Some(error_code), &stack_frame);
+            }
+        )*
+    };
+}
+
+exception_handlers! {
+    divide_error_handler                => DivideError,
+    debug_handler                       => Debug,
+    non_maskable_interrupt_handler      => NonMaskableInterrupt,
+    breakpoint_handler                  => Breakpoint,
+    overflow_handler                    => Overflow,
+    bound_range_exceeded_handler        => BoundRangeExceeded,
+    invalid_opcode_handler              => InvalidOpcode,
+    device_not_available_handler        => DeviceNotAvailable,
+    coprocessor_segment_overrun_handler => CoprocessorSegmentOverrun,
+    x87_floating_point_handler          => X87FloatingPoint,
+    simd_floating_point_handler         => SimdFloatingPoint,
+    virtualization_handler              => Virtualization,
+    hypervisor_injection_handler        => HypervisorInjection,
+}
+
+exception_handlers_with_error_code! {
+    invalid_tss_handler              => InvalidTss,
+    segment_not_present_handler      => SegmentNotPresent,
+    stack_segment_fault_handler      => StackSegmentFault,
+    general_protection_fault_handler => GeneralProtectionFault,
+    alignment_check_handler          => AlignmentCheck,
+    control_protection_handler       => ControlProtection,
+    vmm_communication_handler        => VmmCommunication,
+    security_handler                 => Security,
+}
+
+/// Handles double faults.
+pub extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame, error_code: u64) -> ! {
+    handle_exception(Exception::DoubleFault, Some(error_code), &stack_frame);
+    unreachable!();
+}
+
+/// Handles machine checks.
+pub extern "x86-interrupt" fn machine_check_handler(stack_frame: InterruptStackFrame) -> ! {
+    handle_exception(Exception::MachineCheck, None, &stack_frame);
+    unreachable!();
+}
+
+/// Handles page faults by handing them off to the installed resolver, if any. Faults which are not
+/// resolved are reported and routed through the exception policy like any other exception.
+pub extern "x86-interrupt" fn page_fault_handler(stack_frame: InterruptStackFrame, error_code: PageFaultErrorCode) {
+    let report: PageFaultReport = PageFaultReport {
+        address:     Cr2::read(),
+        error_code,
+        stack_frame: *stack_frame
+    };
+
+    if let Some(resolver) = page_fault_resolver() {
+        if resolver(&report) {
+            return;
+        }
+    }
+
+    let action: ExceptionAction = exception_action(&ExceptionReport {
+        exception:   Exception::PageFault,
+        error_code:  Some(error_code.bits()),
+        stack_frame: *stack_frame
+    });
+
+    print_report(&report);
+    if action == ExceptionAction::Panic {
+        panic!("EXCEPTION: PAGE FAULT at {:#x}", report.address.as_u64());
+    }
+}
+
+
+/*
+ * Page Fault
+ *      Reporting
+ */
+
+
+/// A resolver that page faults are handed off to before being treated as fatal, such as for demand
+/// paging. Returns whether the fault was resolved, in which case the faulting instruction is
+/// restarted.
+/// # Note
+/// Page faults are handled on their own interrupt stack, and a page fault raised within the handler
+/// starts over at the top of that stack, so a resolver must never fault itself.
+pub type PageFaultResolver = fn(&PageFaultReport) -> bool;
+
+/// Installs the resolver that page faults are handed off to, or removes it if `None` is given.
+pub fn set_page_fault_resolver(resolver: Option<PageFaultResolver>) -> () {
+    PAGE_FAULT_RESOLVER.store(resolver.map_or(0, |resolver| resolver as usize), Ordering::Release);
+}
+
+/// Gets the currently installed page fault resolver.
+fn page_fault_resolver() -> Option<PageFaultResolver> {
+    match PAGE_FAULT_RESOLVER.load(Ordering::Acquire) {
+        0        => None,
+        resolver => Some(unsafe { core::mem::transmute::<usize, PageFaultResolver>(resolver) })
+    }
+}
+
+/// Describes a single page fault, decoded from the CR2 register and the error code pushed by the
+/// CPU.
+#[derive(Clone, Copy)]
+pub struct PageFaultReport {
+    
+    /// The virtual address whose access caused the fault, as read from CR2.
+    pub address: VirtAddr,
+
+    /// The raw error code pushed by the CPU.
+    pub error_code: PageFaultErrorCode,
+
+    /// The stack frame of the faulting context.
+    pub stack_frame: InterruptStackFrameValue
+}
+
+impl PageFaultReport {
+
+    /// Whether the fault was caused by a protection violation on a present page, rather than by
+    /// accessing a page that is not present.
+    pub fn is_present(&self) -> bool {
+        self.error_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION)
+    }
+
+    /// Whether the faulting access was a write, rather than a read.
+    pub fn is_write(&self) -> bool {
+        self.error_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE)
+    }
+
+    /// Whether the faulting access was made from user mode (ring 3).
+    pub fn is_user(&self) -> bool {
+        self.error_code.contains(PageFaultErrorCode::USER_MODE)
+    }
+
+    /// Whether the fault was caused by an instruction fetch.
+    pub fn is_instruction_fetch(&self) -> bool {
+        self.error_code.contains(PageFaultErrorCode::INSTRUCTION_FETCH)
+    }
+
+    /// Whether a reserved bit was set in one of the page table entries walked for the access.
+    pub fn is_reserved_bit_set(&self) -> bool {
+        self.error_code.contains(PageFaultErrorCode::MALFORMED_TABLE)
+    }
+}
+
+impl fmt::Display for PageFaultReport {
+    
+    /// Formats the report into a multi-line diagnostic.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access: &str = match (self.is_instruction_fetch(), self.is_write()) {
+            (true, _)     => "instruction fetch",
+            (false, true) => "write",
+            _             => "read"
+        };
+
+        writeln!(f, "EXCEPTION: PAGE FAULT")?;
+        writeln!(f, "    Address:       {:#x}", self.address.as_u64())?;
+        writeln!(f, "    Cause:         {}", if self.is_present() { "protection violation" } else { "page not present" })?;
+        writeln!(f, "    Access:        {access}")?;
+        writeln!(f, "    Privilege:     {}", if self.is_user() { "user" } else { "kernel" })?;
+        writeln!(f, "    Reserved Bit:  {}", if self.is_reserved_bit_set() { "set" } else { "clear" })?;
+        writeln!(f, "    Error Code:    {:#x}", self.error_code.bits())?;
+        write!(f, "{:#?}", self.stack_frame)
+    }
+}
+
+
+/*
+ * Unit
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_breakpoint_exception() -> () {
+    x86_64::instructions::interrupts::int3();
+}
+
+#[test_case]
+fn test_selector_error_code_decoding() -> () {
+    let report: ExceptionReport = ExceptionReport {
+        exception:   Exception::GeneralProtectionFault,
+        error_code:  Some(0x1238),
+        stack_frame: InterruptStackFrameValue {
+            instruction_pointer: VirtAddr::zero(),
+            code_segment:        0,
+            cpu_flags:           0,
+            stack_pointer:       VirtAddr::zero(),
+            stack_segment:       0
+        }
+    };
+
+    let selector: SelectorErrorCode = report.selector_error_code().unwrap();
+    assert_eq!(selector.index(), 0x247);
+    assert!(!selector.external());
+
+    let report: ExceptionReport = ExceptionReport { exception: Exception::AlignmentCheck, ..report };
+    assert!(report.selector_error_code().is_none());
+}
+
+#[test_case]
+fn test_nmi_runs_on_interrupt_stack() -> () {
+    use core::arch::asm;
+    use core::sync::atomic::AtomicBool;
+
+    static ON_NMI_STACK: AtomicBool = AtomicBool::new(false);
+    fn policy(report: &ExceptionReport) -> ExceptionAction {
+        if report.exception == Exception::NonMaskableInterrupt {
+            let marker: u8 = 0;
+            let stack:  &KernelStack = gdt::interrupt_stack(gdt::NMI_IST_INDEX).unwrap();
+            ON_NMI_STACK.store(stack.contains(VirtAddr::from_ptr(&marker)), Ordering::SeqCst);
+        }
+        ExceptionAction::Resume
+    }
+
+    // A software interrupt through the NMI's vector takes the same gate, and so the same stack.
+    set_exception_policy(Some(policy));
+    unsafe { x86_64::software_interrupt!(2) };
+    set_exception_policy(None);
+    assert!(ON_NMI_STACK.load(Ordering::SeqCst), "The NMI handler did not run on its interrupt stack");
+}
+
+#[test_case]
+fn test_interrupt_stacks_distinct() -> () {
+    let indices: [u16; 4] = [gdt::DOUBLE_FAULT_IST_INDEX, gdt::NMI_IST_INDEX, gdt::MACHINE_CHECK_IST_INDEX, gdt::PAGE_FAULT_IST_INDEX];
+    for (i, first) in indices.iter().enumerate() {
+        for second in &indices[i + 1..] {
+            let (first, second): (&KernelStack, &KernelStack) = (gdt::interrupt_stack(*first).unwrap(), gdt::interrupt_stack(*second).unwrap());
+            assert!(!first.contains(second.bottom()) && !second.contains(first.bottom()));
+        }
+    }
+}
+
+#[test_case]
+fn test_default_exception_actions() -> () {
+    assert_eq!(Exception::Breakpoint.default_action(),             ExceptionAction::Resume);
+    assert_eq!(Exception::DivideError.default_action(),            ExceptionAction::Panic);
+    assert_eq!(Exception::GeneralProtectionFault.default_action(), ExceptionAction::Panic);
+}
@@ -22,15 +22,14 @@
 //! spaces.
 //!
 
-use core::ptr::addr_of;
-
-use x86_64::VirtAddr;
 use x86_64::structures::tss::TaskStateSegment;
 use x86_64::structures::gdt::{ Descriptor, GlobalDescriptorTable as GlobalDescTable, SegmentSelector };
 use x86_64::instructions::tables::load_tss;
-use x86_64::instructions::segmentation::{ CS, Segment };
+use x86_64::instructions::segmentation::{ CS, DS, ES, SS, Segment };
 use lazy_static::lazy_static;
 
+use crate::memory::stack::{ self, KernelStack };
+
 
 /*
  * Constant & Static
@@ -38,33 +37,54 @@ use lazy_static::lazy_static;
  */
 
 
-pub const DOUBLE_FAULT_IST_INDEX: u16   = 0;
-    const STACK_SIZE:             usize = 4096 * 5;
+/// The Interrupt Stack Table indices of the exceptions which must always run on a known-good stack,
+/// even if the kernel stack has overflowed or the exception interrupted another handler.
+pub const DOUBLE_FAULT_IST_INDEX:  u16   = 0;
+pub const NMI_IST_INDEX:           u16   = 1;
+pub const MACHINE_CHECK_IST_INDEX: u16   = 2;
+pub const PAGE_FAULT_IST_INDEX:    u16   = 3;
+    const IST_STACKS:              usize = 4;
+    const STACK_PAGES:             u64   = 5;
+    const PRIVILEGE_STACK_PAGES:   u64   = 8;
 
 lazy_static! {
+
+    /// The stacks which the Interrupt Stack Table switches to, each with an unmapped guard page
+    /// below it so that overflowing one faults rather than corrupting adjacent memory.
+    static ref INTERRUPT_STACKS: [KernelStack; IST_STACKS] = core::array::from_fn(|_| {
+        stack::allocate(STACK_PAGES).expect("Failed to allocate an interrupt stack")
+    });
+
+    /// The stack which the CPU switches to whenever an interrupt or exception transitions from user
+    /// mode into the kernel.
+    static ref PRIVILEGE_STACK: KernelStack = stack::allocate(PRIVILEGE_STACK_PAGES).expect("Failed to allocate the privilege stack");
     
     /// A Task State Segment handles the management and switching of kernel stacks in the event
     /// of a unhandleable CPU interruption or exception.
     static ref TSS: TaskStateSegment = {
         let mut tss: TaskStateSegment = TaskStateSegment::new();
-        tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = {
-            static mut STACK: [u8; STACK_SIZE] = [0; STACK_SIZE];
-
-            let stack_start: VirtAddr = VirtAddr::from_ptr(unsafe { addr_of!(STACK) });
-            let stack_end:   VirtAddr = stack_start + STACK_SIZE;
-            stack_end    // Stacks grow downward on x86-64
-        };
+        for (index, stack) in INTERRUPT_STACKS.iter().enumerate() {
+            tss.interrupt_stack_table[index] = stack.top();    // Stacks grow downward on x86-64
+        }
+        tss.privilege_stack_table[0] = PRIVILEGE_STACK.top();
         tss
     };
 
     /// A Global Descriptor Table, which owns the Task State Segment, and handles kernel/user mode
     /// configuration.
+    /// # Note
+    /// The user data segment must directly precede the user code segment, and the kernel data
+    /// segment must directly follow the kernel code segment, as this is the layout that `syscall`
+    /// and `sysret` derive their selectors from.
     static ref GDT: (GlobalDescTable, Selectors) = {
-        let mut gdt:           GlobalDescTable = GlobalDescTable::new();
-        let     code_selector: SegmentSelector = gdt.add_entry(Descriptor::kernel_code_segment());
-        let     tss_selector:  SegmentSelector = gdt.add_entry(Descriptor::tss_segment(&TSS));
+        let mut gdt:                GlobalDescTable = GlobalDescTable::new();
+        let     code_selector:      SegmentSelector = gdt.add_entry(Descriptor::kernel_code_segment());
+        let     data_selector:      SegmentSelector = gdt.add_entry(Descriptor::kernel_data_segment());
+        let     user_data_selector: SegmentSelector = gdt.add_entry(Descriptor::user_data_segment());
+        let     user_code_selector: SegmentSelector = gdt.add_entry(Descriptor::user_code_segment());
+        let     tss_selector:       SegmentSelector = gdt.add_entry(Descriptor::tss_segment(&TSS));
         
-        (gdt, Selectors { code_selector, tss_selector })
+        (gdt, Selectors { code_selector, data_selector, user_code_selector, user_data_selector, tss_selector })
     };
 }
 
@@ -76,9 +96,15 @@ lazy_static! {
 
 
 /// Holds the Global Descriptor Table's selectors.
-struct Selectors {
-    code_selector: SegmentSelector,
-    tss_selector:  SegmentSelector
+/// # Note
+/// The user selectors carry a requested privilege level of 3, so they may be loaded as-is when
+/// entering user mode.
+pub struct Selectors {
+    pub code_selector:      SegmentSelector,
+    pub data_selector:      SegmentSelector,
+    pub user_code_selector: SegmentSelector,
+    pub user_data_selector: SegmentSelector,
+    pub tss_selector:       SegmentSelector
 }
 
 
@@ -89,10 +115,31 @@ struct Selectors {
 
 
 /// Initializes the Global Descriptor Table, along with its own Task State Segment.
+/// # Note
+/// The interrupt stacks are allocated here, so memory must have been initialized beforehand.
 pub fn init() -> () {
     GDT.0.load();
     unsafe {
         CS::set_reg(GDT.1.code_selector);
+        SS::set_reg(GDT.1.data_selector);
+        DS::set_reg(GDT.1.data_selector);
+        ES::set_reg(GDT.1.data_selector);
         load_tss(GDT.1.tss_selector);
     }
 }
+
+/// Gets the Global Descriptor Table's selectors.
+pub fn selectors() -> &'static Selectors {
+    &GDT.1
+}
+
+/// Gets the stack which the Interrupt Stack Table entry of the given index switches to, if the
+/// entry is populated.
+pub fn interrupt_stack(index: u16) -> Option<&'static KernelStack> {
+    INTERRUPT_STACKS.get(index as usize)
+}
+
+/// Gets the stack which the CPU switches to when entering the kernel from user mode.
+pub fn privilege_stack() -> &'static KernelStack {
+    &PRIVILEGE_STACK
+}
@@ -19,14 +19,211 @@
 //?
 
 //!
-//! Handles CPU exceptions (interrupts).
+//! Handles the Interrupt Descriptor Table and hardware interrupts (IRQs), along with the remapping
+//! of the chained Intel 8259 Programmable Interrupt Controllers (PICs).
 //!
 
+use core::sync::atomic::{ AtomicUsize, Ordering };
+
 use x86_64::structures::idt::{ InterruptDescriptorTable as InterruptDescTable, InterruptStackFrame };
+use x86_64::instructions::port::Port;
+use pic8259::ChainedPics;
+use spin::Mutex;
 use lazy_static::lazy_static;
 
-use crate::println;
-use super::gdt;
+use crate::sync::irq_mutex::{ IrqMutex, IrqMutexGuard };
+use super::exceptions;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The interrupt vector that the primary PIC's first line (IRQ 0) is remapped to.
+/// # Note
+/// Vectors 0 through 31 are reserved for CPU exceptions, so the PICs are remapped to the first
+/// free vectors directly after them.
+pub const PIC_1_OFFSET: u8 = 32;
+
+/// The interrupt vector that the secondary PIC's first line (IRQ 8) is remapped to.
+pub const PIC_2_OFFSET: u8 = PIC_1_OFFSET + 8;
+
+/// The amount of IRQ lines provided by the two chained PICs.
+pub const IRQ_LINES: usize = 16;
+
+/// The maximum amount of handlers that may share a single IRQ line.
+pub const MAX_SHARED_HANDLERS: usize = 4;
+
+/// The command ports of the primary and secondary PICs, along with the command used to read their
+/// In-Service Registers.
+const PIC_1_COMMAND:  u16 = 0x20;
+const PIC_2_COMMAND:  u16 = 0xA0;
+const PIC_READ_ISR:   u8  = 0x0B;
+
+/// The two chained 8259 PICs, remapped so that they do not overlap the CPU exception vectors.
+pub static PICS: Mutex<ChainedPics> = Mutex::new(unsafe { ChainedPics::new(PIC_1_OFFSET, PIC_2_OFFSET) });
+
+/// The handlers registered on each IRQ line, stored as raw function pointers so that they may be
+/// read from within an interrupt without taking a lock. A value of zero marks an empty slot.
+static IRQ_HANDLER_TABLE: [[AtomicUsize; MAX_SHARED_HANDLERS]; IRQ_LINES] = [const { [const { AtomicUsize::new(0) }; MAX_SHARED_HANDLERS] }; IRQ_LINES];
+
+/// Serializes changes to the handler table, so that checking for a duplicate handler and claiming a
+/// slot happen as one step. Interrupts are disabled whilst it is held, so that a registration from
+/// within an interrupt can never interleave with one that it interrupted.
+static IRQ_REGISTRATION: IrqMutex<()> = IrqMutex::new(());
+
+
+/*
+ * Interrupt Index
+ *      Enum
+ */
+
+
+/// Encapsulates every IRQ line of the two chained PICs, along with the interrupt vector each line
+/// has been remapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum InterruptIndex {
+
+    // Primary PIC
+    Timer = PIC_1_OFFSET,
+    Keyboard,
+    Cascade,
+    Com2,
+    Com1,
+    Lpt2,
+    FloppyDisk,
+    Lpt1,
+
+    // Secondary PIC
+    RealTimeClock = PIC_2_OFFSET,
+    Acpi,
+    Free1,
+    Free2,
+    Mouse,
+    Coprocessor,
+    PrimaryAta,
+    SecondaryAta
+}
+
+impl InterruptIndex {
+
+    /// Every IRQ line, ordered by its line number.
+    pub const ALL: [Self; IRQ_LINES] = [
+        Self::Timer,         Self::Keyboard, Self::Cascade, Self::Com2,  Self::Com1,  Self::Lpt2,        Self::FloppyDisk, Self::Lpt1,
+        Self::RealTimeClock, Self::Acpi,     Self::Free1,   Self::Free2, Self::Mouse, Self::Coprocessor, Self::PrimaryAta, Self::SecondaryAta
+    ];
+
+    /// Gets the IRQ line from its line number (0 through 15).
+    pub fn from_line(line: u8) -> Option<Self> {
+        Self::ALL.get(line as usize).copied()
+    }
+
+    /// Gets the interrupt vector this IRQ line has been remapped to.
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Gets the interrupt vector this IRQ line has been remapped to, as an index into the IDT.
+    pub fn as_usize(self) -> usize {
+        self as usize
+    }
+
+    /// Gets the raw IRQ line number (0 through 15).
+    pub fn line(self) -> u8 {
+        self.as_u8() - PIC_1_OFFSET
+    }
+}
+
+
+/*
+ * IRQ Handler
+ *      Registration
+ */
+
+
+/// A handler for a hardware interrupt, which is passed the IRQ line that was raised so that a
+/// single handler may serve multiple lines.
+pub type IrqHandler = fn(InterruptIndex) -> ();
+
+/// The errors that may occur whilst registering or unregistering an IRQ handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrqError {
+    
+    /// Every slot on the IRQ line is already taken by another handler.
+    LineFull,
+
+    /// The handler is already registered on the IRQ line.
+    AlreadyRegistered,
+
+    /// The handler is not registered on the IRQ line.
+    NotRegistered
+}
+
+/// Registers a handler on an IRQ line, unmasking the line on the PICs if it is the first handler to
+/// claim it.
+/// # Note
+/// Up to `MAX_SHARED_HANDLERS` handlers may share a single line, and each is called in turn whenever the
+/// line is raised. As the end of the interrupt is signalled after every
+/// handler has returned, handlers must not call `end_of_interrupt` themselves.
+pub fn register_irq(index: InterruptIndex, handler: IrqHandler) -> Result<(), IrqError> {
+    let slots:   &[AtomicUsize; MAX_SHARED_HANDLERS] = &IRQ_HANDLER_TABLE[index.line() as usize];
+    let handler: usize                               = handler as usize;
+    let _guard:  IrqMutexGuard<()>                   = IRQ_REGISTRATION.lock();
+    
+    if slots.iter().any(|slot| slot.load(Ordering::Acquire) == handler) {
+        return Err(IrqError::AlreadyRegistered);
+    }
+
+    for slot in slots {
+        if slot.compare_exchange(0, handler, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+            set_line_masked(index, false);
+            return Ok(());
+        }
+    }
+    Err(IrqError::LineFull)
+}
+
+/// Unregisters a handler from an IRQ line, masking the line on the PICs if no other handler
+/// remains on it.
+pub fn unregister_irq(index: InterruptIndex, handler: IrqHandler) -> Result<(), IrqError> {
+    let slots:   &[AtomicUsize; MAX_SHARED_HANDLERS] = &IRQ_HANDLER_TABLE[index.line() as usize];
+    let handler: usize                               = handler as usize;
+    let _guard:  IrqMutexGuard<()>                   = IRQ_REGISTRATION.lock();
+
+    let removed: bool = slots.iter().any(|slot| slot.compare_exchange(handler, 0, Ordering::AcqRel, Ordering::Acquire).is_ok());
+    if !removed {
+        return Err(IrqError::NotRegistered);
+    }
+    
+    if index != InterruptIndex::Cascade && slots.iter().all(|slot| slot.load(Ordering::Acquire) == 0) {
+        set_line_masked(index, true);
+    }
+    Ok(())
+}
+
+/// Masks or unmasks a single IRQ line on the PICs, so that the line is ignored or delivered
+/// respectively.
+pub fn set_line_masked(index: InterruptIndex, masked: bool) -> () {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let mut pics:  spin::MutexGuard<ChainedPics> = PICS.lock();
+        let mut masks: [u8; 2]                       = unsafe { pics.read_masks() };
+
+        let line: u8      = index.line();
+        let mask: &mut u8 = &mut masks[(line / 8) as usize];
+        if masked {
+            *mask |= 1 << (line % 8);
+        } else {
+            *mask &= !(1 << (line % 8));
+        }
+
+        unsafe {
+            pics.write_masks(masks[0], masks[1]);
+        }
+    });
+}
 
 
 /*
@@ -40,9 +237,10 @@ lazy_static! {
     /// A static InterruptDescriptorTable that has the same lifetime as the kernel.
     static ref IDT: InterruptDescTable = {
         let mut idt: InterruptDescTable = InterruptDescTable::new();
-        idt.breakpoint.set_handler_fn(breakpoint_handler);
-        unsafe {
-            idt.double_fault.set_handler_fn(double_fault_handler).set_stack_index(gdt::DOUBLE_FAULT_IST_INDEX);
+        exceptions::set_handlers(&mut idt);
+
+        for (index, handler) in InterruptIndex::ALL.iter().zip(IRQ_HANDLERS) {
+            idt[index.as_usize()].set_handler_fn(handler);
         }
         idt
     };
@@ -53,21 +251,107 @@ pub fn init_idt() {
     IDT.load();
 }
 
+/// Initializes and remaps the two chained PICs.
+/// # Note
+/// Every line apart from the cascade line is masked until a handler is registered on it, and this
+/// does not enable interrupts on the CPU itself.
+pub fn init_pics() -> () {
+    let mut pics: spin::MutexGuard<ChainedPics> = PICS.lock();
+    unsafe {
+        pics.initialize();
+        pics.write_masks(!(1 << InterruptIndex::Cascade.line()), 0xFF);
+    }
+}
+
 
 /*
- * Exception
+ * Hardware Interrupt
  *      Handlers
  */
 
 
-/// Handles breakpoints in CPU execution.
-extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
-    println!("EXCEPTION: BREAKPOINT\n{stack_frame:#?}");
+/// Generates an interrupt handler for each of the given IRQ lines which trampolines into the
+/// handlers registered on that line, along with a table of said trampolines ordered by their line
+/// number.
+macro_rules! irq_handlers {
+    ($($name:ident => $index:ident),* $(,)?) => {
+        $(
+            extern "x86-interrupt" fn $name(_stack_frame: InterruptStackFrame) {
+                dispatch_irq(InterruptIndex::$index);
+            }
+        )*
+
+        /// Every hardware interrupt trampoline, ordered by its IRQ line.
+        const IRQ_HANDLERS: [extern "x86-interrupt" fn(InterruptStackFrame); IRQ_LINES] = [$($name),*];
+    };
 }
 
-/// Handles double faults.
-extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame, _error_code: u64) -> ! {
-    panic!("EXCEPTION: DOUBLE FAULT\n{stack_frame:#?}");
+irq_handlers! {
+    irq_0_handler  => Timer,
+    irq_1_handler  => Keyboard,
+    irq_2_handler  => Cascade,
+    irq_3_handler  => Com2,
+    irq_4_handler  => Com1,
+    irq_5_handler  => Lpt2,
+    irq_6_handler  => FloppyDisk,
+    irq_7_handler  => Lpt1,
+    irq_8_handler  => RealTimeClock,
+    irq_9_handler  => Acpi,
+    irq_10_handler => Free1,
+    irq_11_handler => Free2,
+    irq_12_handler => Mouse,
+    irq_13_handler => Coprocessor,
+    irq_14_handler => PrimaryAta,
+    irq_15_handler => SecondaryAta,
+}
+
+/// Calls every handler registered on the raised IRQ line before signalling the end of the
+/// interrupt.
+/// # Note
+/// IRQ 7 and IRQ 15 may be raised spuriously, in which case the line is not marked as in service in
+/// its PIC's In-Service Register, no handler is called, and no end of interrupt may be sent to that
+/// PIC. A spurious IRQ 15 still requires the primary PIC to be notified, as the cascade line was
+/// legitimately raised.
+fn dispatch_irq(index: InterruptIndex) -> () {
+    match index {
+        InterruptIndex::Lpt1         if !is_in_service(PIC_1_COMMAND, index.line())     => return,
+        InterruptIndex::SecondaryAta if !is_in_service(PIC_2_COMMAND, index.line() - 8) => {
+            end_of_interrupt(InterruptIndex::Cascade);
+            return;
+        },
+        _ => ()
+    }
+
+    call_handlers(index);
+    end_of_interrupt(index);
+}
+
+/// Calls every handler registered on an IRQ line in turn.
+fn call_handlers(index: InterruptIndex) -> () {
+    for slot in &IRQ_HANDLER_TABLE[index.line() as usize] {
+        let handler: usize = slot.load(Ordering::Acquire);
+        if handler != 0 {
+            let handler: IrqHandler = unsafe { core::mem::transmute::<usize, IrqHandler>(handler) };
+            handler(index);
+        }
+    }
+}
+
+/// Signals the end of an interrupt to the PICs so that they may deliver further interrupts.
+pub fn end_of_interrupt(index: InterruptIndex) -> () {
+    unsafe {
+        PICS.lock().notify_end_of_interrupt(index.as_u8());
+    }
+}
+
+/// Reads whether a line of a PIC is currently marked in its In-Service Register.
+fn is_in_service(command_port: u16, line: u8) -> bool {
+    let mut port: Port<u8> = Port::new(command_port);
+    let isr: u8 = unsafe {
+        port.write(PIC_READ_ISR);
+        port.read()
+    };
+    isr & (1 << line) != 0
 }
 
 
@@ -78,6 +362,99 @@ extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame,
 
 
 #[test_case]
-fn test_breakpoint_exception() -> () {
-    x86_64::instructions::interrupts::int3();
+fn test_irq_lines_remapped() -> () {
+    for (line, index) in InterruptIndex::ALL.iter().enumerate() {
+        assert!(index.as_u8() >= 32, "IRQ {line} overlaps the CPU exception vectors");
+        assert_eq!(index.line() as usize, line);
+        assert_eq!(InterruptIndex::from_line(line as u8), Some(*index));
+    }
+}
+
+#[test_case]
+fn test_interrupts_enabled() -> () {
+    assert!(x86_64::instructions::interrupts::are_enabled());
+}
+
+
+
+/*
+ * IRQ Registration
+ *      Tests
+ *
+ *  # Note:
+ *  These tests mask their lines and call the handlers directly, rather than raising the line's
+ *  vector with a software interrupt, as the dispatcher would then signal an end of interrupt to a
+ *  PIC that never raised the IRQ.
+ */
+
+
+#[test_case]
+fn test_register_irq() -> () {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    fn handler(index: InterruptIndex) -> () {
+        assert_eq!(index, InterruptIndex::Free1);
+        CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    register_irq(InterruptIndex::Free1, handler).unwrap();
+    set_line_masked(InterruptIndex::Free1, true);
+    assert_eq!(register_irq(InterruptIndex::Free1, handler), Err(IrqError::AlreadyRegistered));
+    
+    call_handlers(InterruptIndex::Free1);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    unregister_irq(InterruptIndex::Free1, handler).unwrap();
+    assert_eq!(unregister_irq(InterruptIndex::Free1, handler), Err(IrqError::NotRegistered));
+
+    call_handlers(InterruptIndex::Free1);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[test_case]
+fn test_shared_irq_chaining() -> () {
+    static ORDER: AtomicUsize = AtomicUsize::new(0);
+    fn first(_: InterruptIndex) -> () {
+        ORDER.fetch_add(1, Ordering::SeqCst);
+    }
+    fn second(_: InterruptIndex) -> () {
+        ORDER.fetch_add(10, Ordering::SeqCst);
+    }
+
+    register_irq(InterruptIndex::Free2, first).unwrap();
+    register_irq(InterruptIndex::Free2, second).unwrap();
+    set_line_masked(InterruptIndex::Free2, true);
+
+    call_handlers(InterruptIndex::Free2);
+    assert_eq!(ORDER.load(Ordering::SeqCst), 11);
+
+    unregister_irq(InterruptIndex::Free2, first).unwrap();
+    call_handlers(InterruptIndex::Free2);
+    assert_eq!(ORDER.load(Ordering::SeqCst), 21);
+    
+    unregister_irq(InterruptIndex::Free2, second).unwrap();
+}
+
+#[test_case]
+fn test_irq_line_full() -> () {
+    // Each handler stores a different value, so that no two may be folded into one function.
+    static LAST: AtomicUsize = AtomicUsize::new(0);
+    fn handler_a(_: InterruptIndex) -> () { LAST.store(1, Ordering::SeqCst); }
+    fn handler_b(_: InterruptIndex) -> () { LAST.store(2, Ordering::SeqCst); }
+    fn handler_c(_: InterruptIndex) -> () { LAST.store(3, Ordering::SeqCst); }
+    fn handler_d(_: InterruptIndex) -> () { LAST.store(4, Ordering::SeqCst); }
+    fn handler_e(_: InterruptIndex) -> () { LAST.store(5, Ordering::SeqCst); }
+    
+    let handlers: [IrqHandler; MAX_SHARED_HANDLERS] = [handler_a, handler_b, handler_c, handler_d];
+    for handler in handlers {
+        register_irq(InterruptIndex::Lpt2, handler).unwrap();
+    }
+    set_line_masked(InterruptIndex::Lpt2, true);
+    assert_eq!(register_irq(InterruptIndex::Lpt2, handler_e), Err(IrqError::LineFull));
+
+    call_handlers(InterruptIndex::Lpt2);
+    assert_eq!(LAST.load(Ordering::SeqCst), 4);
+
+    for handler in handlers {
+        unregister_irq(InterruptIndex::Lpt2, handler).unwrap();
+    }
 }
@@ -1,3 +1,6 @@
 
 pub mod interrupts;
+pub mod exceptions;
 pub mod gdt;
+pub mod user_mode;
+pub mod syscall;
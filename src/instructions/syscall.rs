@@ -0,0 +1,321 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$                                          /$$ /$$
+//  /$$__  $$                                        | $$| $$
+// | $$  \__/ /$$   /$$  /$$$$$$$  /$$$$$$$  /$$$$$$ | $$| $$
+// |  $$$$$$ | $$  | $$ /$$_____/ /$$_____/ |____  $$| $$| $$
+//  \____  $$| $$  | $$|  $$$$$$ | $$        /$$$$$$$| $$| $$
+//  /$$  \ $$| $$  | $$ \____  $$| $$       /$$__  $$| $$| $$
+// |  $$$$$$/|  $$$$$$$ /$$$$$$$/|  $$$$$$$|  $$$$$$$| $$| $$
+//  \______/  \____  $$|_______/  \_______/ \_______/|__/|__/
+//            /$$  | $$                                      
+//           |  $$$$$$/                                      
+//            \______/                                       
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! The system call interface, through which code running in user mode requests services from the
+//! kernel with the `syscall` instruction.
+//!
+//! # Register ABI
+//! The call number is passed in `rax`, and up to six arguments in `rdi`, `rsi`, `rdx`, `r10`, `r8`
+//! and `r9`. The result is returned in `rax`, where values from `-4095` to `-1` are negated
+//! `SyscallError` codes. `rcx` and `r11` are clobbered by the `syscall` instruction itself, and
+//! every other register is preserved.
+//!
+
+use core::arch::global_asm;
+use core::sync::atomic::{ AtomicU64, Ordering };
+use core::{ fmt, slice };
+
+use x86_64::VirtAddr;
+use x86_64::registers::model_specific::{ Efer, EferFlags, LStar, SFMask, Star };
+use x86_64::registers::rflags::RFlags;
+use x86_64::structures::paging::PageTableFlags;
+
+use crate::drivers::{ vga_text::WRITER, serial::{ SERIAL_1, Uart } };
+use crate::sync::irq_mutex::IrqMutexGuard;
+use crate::memory::paging;
+use super::{ gdt, user_mode };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The system call numbers.
+pub const SYS_WRITE:  u64 = 0;
+pub const SYS_EXIT:   u64 = 1;
+pub const SYS_GETPID: u64 = 2;
+
+/// The file descriptors which may be written to.
+pub const STDOUT: u64 = 1;
+pub const STDERR: u64 = 2;
+
+/// The process identifier reported to user mode, as every user mode context currently runs as the
+/// first process.
+pub const INIT_PID: u64 = 1;
+
+/// The highest address, exclusive, that user mode may hand to the kernel.
+const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// The handlers of each system call, indexed by call number.
+static SYSCALL_TABLE: [Option<SyscallHandler>; 3] = [
+    Some(sys_write),
+    Some(sys_exit),
+    Some(sys_getpid)
+];
+
+/// The stack pointers swapped between on entry into and exit from a system call.
+/// # Note
+/// These are only ever touched with interrupts disabled on a single core; supporting more cores
+/// would require moving them into per-core storage.
+static SYSCALL_KERNEL_RSP: AtomicU64 = AtomicU64::new(0);
+static SYSCALL_USER_RSP:   AtomicU64 = AtomicU64::new(0);
+
+
+/*
+ * Syscall Error &
+ *      Frame
+ */
+
+
+/// The reasons why a system call may fail, which are returned to user mode negated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u64)]
+pub enum SyscallError {
+
+    /// No system call exists with the given number.
+    NoSuchCall = 1,
+
+    /// A pointer argument does not point to memory that user mode may access.
+    BadAddress = 2,
+
+    /// An argument is out of range or otherwise malformed.
+    InvalidArgument = 3,
+
+    /// The file descriptor does not refer to anything.
+    BadDescriptor = 4
+}
+
+impl SyscallError {
+
+    /// Every system call error, in order.
+    pub const ALL: [Self; 4] = [Self::NoSuchCall, Self::BadAddress, Self::InvalidArgument, Self::BadDescriptor];
+
+    /// Encodes the error as the value returned to user mode in `rax`.
+    pub fn encode(self) -> u64 {
+        (self as u64).wrapping_neg()
+    }
+
+    /// Decodes a value returned from a system call into its result or error.
+    pub fn decode(value: u64) -> Result<u64, Self> {
+        match Self::ALL.iter().find(|error| error.encode() == value) {
+            Some(error) => Err(*error),
+            None        => Ok(value)
+        }
+    }
+}
+
+impl fmt::Display for SyscallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSuchCall      => write!(f, "no such system call"),
+            Self::BadAddress      => write!(f, "bad address"),
+            Self::InvalidArgument => write!(f, "invalid argument"),
+            Self::BadDescriptor   => write!(f, "bad file descriptor")
+        }
+    }
+}
+
+/// The registers a system call was made with, as pushed by the entry stub.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct SyscallFrame {
+    pub number: u64,
+    pub args:   [u64; 6]
+}
+
+/// A system call handler, which receives the registers of the call.
+pub type SyscallHandler = fn(&SyscallFrame) -> Result<u64, SyscallError>;
+
+
+/*
+ * Entry &
+ *      Dispatch
+ */
+
+
+// `syscall` leaves the stack pointer untouched, so the entry stub swaps over to the kernel stack
+// itself before saving the return address (`rcx`), the flags (`r11`) and the arguments, and
+// dispatching with a pointer to the saved frame. The arguments are then restored and `sysretq`
+// returns to user mode with the result in `rax`. On Intel CPUs, `sysretq` raises #GP in ring 0
+// (and on the user's stack) if the return address is not canonical, so such a return instead ends
+// the user mode context before the user's stack is restored.
+global_asm!(
+    ".global solas_syscall_entry",
+    "solas_syscall_entry:",
+    "    mov [rip + {user_rsp}], rsp",
+    "    mov rsp, [rip + {kernel_rsp}]",
+    "    push [rip + {user_rsp}]",
+    "    push rcx",
+    "    push r11",
+    "    push r9",
+    "    push r8",
+    "    push r10",
+    "    push rdx",
+    "    push rsi",
+    "    push rdi",
+    "    push rax",
+    "    mov rdi, rsp",
+    "    call {dispatch}",
+    "    add rsp, 8",
+    "    pop rdi",
+    "    pop rsi",
+    "    pop rdx",
+    "    pop r10",
+    "    pop r8",
+    "    pop r9",
+    "    pop r11",
+    "    pop rcx",
+    "    push rcx",       // A canonical address is unchanged by sign extending its low 48 bits.
+    "    shl rcx, 16",
+    "    sar rcx, 16",
+    "    cmp rcx, [rsp]",
+    "    pop rcx",
+    "    jne {non_canonical}",
+    "    pop rsp",
+    "    sysretq",
+    user_rsp      = sym SYSCALL_USER_RSP,
+    kernel_rsp    = sym SYSCALL_KERNEL_RSP,
+    dispatch      = sym dispatch,
+    non_canonical = sym end_non_canonical_return
+);
+
+extern "C" {
+    fn solas_syscall_entry();
+}
+
+/// Looks up the handler of a system call and runs it, encoding its result for user mode.
+extern "C" fn dispatch(frame: &SyscallFrame) -> u64 {
+    let handler: Option<SyscallHandler> = SYSCALL_TABLE.get(frame.number as usize).copied().flatten();
+    match handler.ok_or(SyscallError::NoSuchCall).and_then(|handler| handler(frame)) {
+        Ok(value)  => value,
+        Err(error) => error.encode()
+    }
+}
+
+/// Ends the user mode context of a system call whose return address is not canonical, as `sysretq`
+/// cannot safely return to it. `user_mode::enter` then returns `SyscallError::BadAddress`, encoded.
+extern "C" fn end_non_canonical_return() -> ! {
+    unsafe { user_mode::return_to_kernel(SyscallError::BadAddress.encode()) }
+}
+
+/// Enables the `syscall` and `sysret` instructions, pointing them at the entry stub and the GDT's
+/// selectors.
+/// # Note
+/// System calls run on the privilege stack with interrupts disabled, so the GDT must have been
+/// initialized beforehand.
+pub fn init() -> () {
+    let selectors: &gdt::Selectors = gdt::selectors();
+    SYSCALL_KERNEL_RSP.store(gdt::privilege_stack().top().as_u64(), Ordering::SeqCst);
+    
+    Star::write(selectors.user_code_selector, selectors.user_data_selector, selectors.code_selector, selectors.data_selector)
+        .expect("The GDT's selectors are not laid out for sysret");
+    LStar::write(VirtAddr::new(solas_syscall_entry as usize as u64));
+    SFMask::write(RFlags::INTERRUPT_FLAG | RFlags::DIRECTION_FLAG | RFlags::TRAP_FLAG);
+    unsafe { Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS)) };
+}
+
+
+/*
+ * System Call
+ *      Handlers
+ */
+
+
+/// Writes a buffer of bytes to a file descriptor unchanged, returning the amount of bytes written.
+/// `write(fd, buffer, length)`
+fn sys_write(frame: &SyscallFrame) -> Result<u64, SyscallError> {
+    let [fd, buffer, length, ..] = frame.args;
+    let bytes: &[u8] = user_slice(buffer, length)?;
+    match fd {
+        STDOUT => WRITER.lock().write_bytes(bytes),
+        STDERR => {
+            let mut serial: IrqMutexGuard<Uart> = SERIAL_1.lock();
+            for byte in bytes {
+                serial.send(*byte);
+            }
+        },
+        _      => return Err(SyscallError::BadDescriptor)
+    }
+    Ok(length)
+}
+
+/// Ends the user mode context, returning the exit code to the kernel.
+/// `exit(code)`
+fn sys_exit(frame: &SyscallFrame) -> Result<u64, SyscallError> {
+    unsafe { user_mode::return_to_kernel(frame.args[0]) }
+}
+
+/// Gets the identifier of the calling process.
+/// `getpid()`
+fn sys_getpid(_: &SyscallFrame) -> Result<u64, SyscallError> {
+    Ok(INIT_PID)
+}
+
+/// Borrows a buffer handed over from user mode, checking that every page of it is mapped and user
+/// accessible at every level of the page table hierarchy.
+fn user_slice(address: u64, length: u64) -> Result<&'static [u8], SyscallError> {
+    let end: u64 = address.checked_add(length).filter(|end| *end <= USER_SPACE_END).ok_or(SyscallError::BadAddress)?;
+    if length == 0 {
+        return Ok(&[]);
+    }
+
+    let mut page: u64 = address & !0xfff;
+    while page < end {
+        let flags: PageTableFlags = paging::effective_flags(VirtAddr::new(page)).ok_or(SyscallError::BadAddress)?;
+        if !flags.contains(PageTableFlags::USER_ACCESSIBLE) {
+            return Err(SyscallError::BadAddress);
+        }
+        page += 0x1000;
+    }
+    Ok(unsafe { slice::from_raw_parts(address as *const u8, length as usize) })
+}
+
+
+/*
+ * Syscall
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_error_encoding() -> () {
+    for error in SyscallError::ALL {
+        assert_eq!(SyscallError::decode(error.encode()), Err(error));
+    }
+    assert_eq!(SyscallError::decode(0), Ok(0));
+    assert_eq!(SyscallError::decode(u64::MAX - 4096), Ok(u64::MAX - 4096));
+}
+
+#[test_case]
+fn test_dispatch_from_kernel() -> () {
+    let frame: SyscallFrame = SyscallFrame { number: SYS_GETPID, args: [0; 6] };
+    assert_eq!(dispatch(&frame), INIT_PID);
+
+    let frame: SyscallFrame = SyscallFrame { number: 0xffff, args: [0; 6] };
+    assert_eq!(SyscallError::decode(dispatch(&frame)), Err(SyscallError::NoSuchCall));
+
+    // Kernel memory is never user accessible, so writes from it must be refused.
+    let message: &str         = "kernel";
+    let frame:   SyscallFrame = SyscallFrame { number: SYS_WRITE, args: [STDOUT, message.as_ptr() as u64, message.len() as u64, 0, 0, 0] };
+    assert_eq!(SyscallError::decode(dispatch(&frame)), Err(SyscallError::BadAddress));
+}
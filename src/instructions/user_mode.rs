@@ -0,0 +1,128 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                                     /$$      /$$                 /$$          
+// | $$  | $$                                    | $$$    /$$$                | $$          
+// | $$  | $$  /$$$$$$$  /$$$$$$   /$$$$$$       | $$$$  /$$$$  /$$$$$$   /$$$$$$$  /$$$$$$ 
+// | $$  | $$ /$$_____/ /$$__  $$ /$$__  $$      | $$ $$/$$ $$ /$$__  $$ /$$__  $$ /$$__  $$
+// | $$  | $$|  $$$$$$ | $$$$$$$$| $$  \__/      | $$  $$$| $$| $$  \ $$| $$  | $$| $$$$$$$$
+// | $$  | $$ \____  $$| $$_____/| $$            | $$\  $ | $$| $$  | $$| $$  | $$| $$_____/
+// |  $$$$$$/ /$$$$$$$/|  $$$$$$$| $$            | $$ \/  | $$|  $$$$$$/|  $$$$$$$|  $$$$$$$
+//  \______/ |_______/  \_______/|__/            |__/     |__/ \______/  \_______/ \_______/
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Transfers control from the kernel into code running in user mode (ring 3), and back again once
+//! that code is done, such as when it exits or faults.
+//!
+
+use core::arch::global_asm;
+use core::sync::atomic::AtomicU64;
+
+use x86_64::VirtAddr;
+
+use super::gdt;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The kernel stack pointer saved upon entering user mode, which is restored once control returns
+/// to the kernel.
+static KERNEL_RETURN_RSP: AtomicU64 = AtomicU64::new(0);
+
+
+/*
+ * Mode Switch
+ *      Routines
+ */
+
+
+// Entering user mode saves the kernel's callee-saved registers and flags on the kernel stack,
+// records the stack pointer, and then builds an interrupt stack frame for `iretq` to return into
+// ring 3 with. Returning to the kernel simply restores that stack pointer and unwinds the saved
+// registers, so that `enter` appears to return the given value.
+global_asm!(
+    ".global solas_enter_user_mode",
+    "solas_enter_user_mode:",
+    "    pushfq",
+    "    push rbx",
+    "    push rbp",
+    "    push r12",
+    "    push r13",
+    "    push r14",
+    "    push r15",
+    "    mov [rip + {saved_rsp}], rsp",
+    "    push rcx",       // SS
+    "    push rsi",       // RSP
+    "    push 0x202",     // RFLAGS, with interrupts enabled
+    "    push rdx",       // CS
+    "    push rdi",       // RIP
+    "    xor eax, eax",   // Leave no kernel values behind in the registers.
+    "    xor ebx, ebx",
+    "    xor ecx, ecx",
+    "    xor edx, edx",
+    "    xor esi, esi",
+    "    xor edi, edi",
+    "    xor ebp, ebp",
+    "    xor r8d, r8d",
+    "    xor r9d, r9d",
+    "    xor r10d, r10d",
+    "    xor r11d, r11d",
+    "    xor r12d, r12d",
+    "    xor r13d, r13d",
+    "    xor r14d, r14d",
+    "    xor r15d, r15d",
+    "    iretq",
+    "",
+    ".global solas_return_to_kernel",
+    "solas_return_to_kernel:",
+    "    mov rsp, [rip + {saved_rsp}]",
+    "    mov rax, rdi",
+    "    pop r15",
+    "    pop r14",
+    "    pop r13",
+    "    pop r12",
+    "    pop rbp",
+    "    pop rbx",
+    "    popfq",
+    "    ret",
+    saved_rsp = sym KERNEL_RETURN_RSP
+);
+
+extern "C" {
+    fn solas_enter_user_mode(entry: u64, stack_top: u64, code_selector: u64, data_selector: u64) -> u64;
+    fn solas_return_to_kernel(value: u64) -> !;
+}
+
+/// Enters user mode at the given entry point with the given stack, and returns the value that is
+/// later handed to `return_to_kernel`.
+/// # Safety
+/// The entry point and the whole stack must be mapped as user accessible, the GDT must have been
+/// initialized, and only one user mode context may be entered at a time.
+pub unsafe fn enter(entry: VirtAddr, stack_top: VirtAddr) -> u64 {
+    let selectors: &gdt::Selectors = gdt::selectors();
+    solas_enter_user_mode(
+        entry.as_u64(),
+        stack_top.as_u64(),
+        selectors.user_code_selector.0 as u64,
+        selectors.user_data_selector.0 as u64
+    )
+}
+
+/// Abandons the current user mode context and resumes the kernel from where it called `enter`,
+/// which then returns the given value.
+/// # Safety
+/// This must only be called from the kernel whilst handling an interrupt, exception or system call
+/// raised by the user mode context that `enter` started. Anything held on the current stack, such
+/// as locks, is never released.
+pub unsafe fn return_to_kernel(value: u64) -> ! {
+    solas_return_to_kernel(value)
+}
@@ -25,18 +25,28 @@
 #![no_main]
 #![no_std]
 
-#![feature(custom_test_frameworks, abi_x86_interrupt)]
+#![feature(custom_test_frameworks, abi_x86_interrupt, asm_const, alloc_error_handler, const_mut_refs)]
 #![test_runner(crate::test_runner)]
 #![reexport_test_harness_main = "test_main"]
 
+extern crate alloc;
+
 pub mod instructions;
 pub mod drivers;
+pub mod sync;
+pub mod memory;
 
 use core::{ panic::PanicInfo, any::type_name };
 
 use x86_64::instructions::port::Port;
+use x86_64::structures::idt::{ InterruptDescriptorTable as InterruptDescTable, InterruptStackFrame };
+use bootloader::BootInfo;
+use spin::Once;
+#[cfg(test)]
+use bootloader::entry_point;
 
-use instructions::{ interrupts, gdt };
+use instructions::{ interrupts, gdt, syscall, exceptions::{ self, Exception, ExceptionPolicy } };
+use drivers::{ pit, keyboard, serial };
 
 
 /*
@@ -45,10 +55,28 @@ use instructions::{ interrupts, gdt };
  */
 
 
-/// A global kernel initialization function.
-pub fn init() -> () {
+/// A global kernel initialization function, which consumes the information handed over by the
+/// bootloader.
+pub fn init(boot_info: &'static BootInfo) -> () {
+    memory::init(boot_info);
     interrupts::init_idt();
     gdt::init();
+    syscall::init();
+    interrupts::init_pics();
+    pit::init(pit::DEFAULT_FREQUENCY).expect("Failed to register the timer interrupt");
+    keyboard::init(keyboard::ScancodeSet::Set1).expect("Failed to register the keyboard interrupt");
+    serial::init().expect("Failed to register the serial interrupt");
+    x86_64::instructions::interrupts::enable();
+}
+
+/// Halts the CPU until the next interrupt arrives, forever.
+/// # Note
+/// This should be preferred over an empty `loop {}`, as the CPU is left idle in between interrupts
+/// rather than spinning at full load.
+pub fn hlt_loop() -> ! {
+    loop {
+        x86_64::instructions::hlt();
+    }
 }
 
 
@@ -80,13 +108,15 @@ impl <T: Fn()> UnitTest for T {
     }
 }
 
+#[cfg(test)]
+entry_point!(test_kernel_main);
+
 /// Entry Point for Unit Tests
 #[cfg(test)]
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
-    init();
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    init(boot_info);
     test_main();
-    loop {}
+    hlt_loop();
 }
 
 /// Panic Handler for Unit Tests
@@ -98,10 +128,10 @@ fn panic(info: &PanicInfo) -> ! {
 
 /// Panic Handler for Unit Test Execution
 pub fn test_panic_handler(info: &PanicInfo) -> ! {
-    serial_println!("[failed]");
-    serial_println!("Error: {}", info);
+    // The panic may have interrupted a print, whose lock would then never be released.
+    unsafe { serial::force_print(format_args!("[failed]\nError: {}\n", info)) };
     test_terminate(QemuExitCode::Failed);
-    loop {}
+    hlt_loop();
 }
 
 /// Test Runner
@@ -128,3 +158,48 @@ pub fn test_terminate(exit_code: QemuExitCode) -> () {
     }
 }
 
+
+/*
+ * Exception Test
+ *      Harness
+ */
+
+
+/// The IDT loaded by an exception test, which only holds the handlers of the exceptions under test.
+static EXCEPTION_TEST_IDT: Once<InterruptDescTable> = Once::new();
+
+/// Prepares the kernel for an integration test which raises the given exceptions. The GDT is loaded
+/// alongside an IDT which only holds the OS's handlers for those exceptions, so that any other
+/// exception escalates to a double fault and fails the test, and the given policy is installed.
+pub fn init_exception_test(boot_info: &'static BootInfo, tested: &[Exception], policy: ExceptionPolicy) -> () {
+    memory::init(boot_info);
+    gdt::init();
+    EXCEPTION_TEST_IDT.call_once(|| {
+        let mut idt: InterruptDescTable = InterruptDescTable::new();
+        for exception in tested {
+            exceptions::set_handler(&mut idt, *exception);
+        }
+        unsafe {
+            idt.double_fault
+                .set_handler_fn(exception_test_double_fault_handler)
+                .set_stack_index(gdt::DOUBLE_FAULT_IST_INDEX);
+        }
+        idt
+    }).load();
+    exceptions::set_exception_policy(Some(policy));
+}
+
+/// Runs the single test of an exception test, which passes if it returns.
+pub fn run_exception_test(name: &str, test: fn() -> ()) -> ! {
+    serial_println!("Running 1 test");
+    serial_print!("{}...\t", name);
+    test();
+    serial_println!("[ok]");
+    test_terminate(QemuExitCode::Success);
+    hlt_loop();
+}
+
+/// Fails an exception test once a different exception has escalated into a double fault.
+extern "x86-interrupt" fn exception_test_double_fault_handler(_: InterruptStackFrame, _: u64) -> ! {
+    panic!("A double fault was raised instead of the exception under test!");
+}
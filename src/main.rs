@@ -31,7 +31,10 @@
 
 use core::panic::PanicInfo;
 
+use bootloader::{ BootInfo, entry_point };
+
 use solas_os::println;
+use solas_os::memory::map;
 
 
 /*
@@ -40,20 +43,24 @@ use solas_os::println;
  */
 
 
+entry_point!(kernel_main);
+
 /// Entry Point
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
+fn kernel_main(boot_info: &'static BootInfo) -> ! {
     println!("Hello World{}", "!");
 
     // Initialize the kernel.
-    solas_os::init();    
+    solas_os::init(boot_info);
+    if let Some(memory_map) = map::memory_map() {
+        println!("{memory_map}");
+    }
 
     // Handle unit tests if we have any.
     #[cfg(test)]
     test_main();
 
     // Do other stuff...
-    loop {}
+    solas_os::hlt_loop();
 }
 
 
@@ -67,8 +74,8 @@ pub extern "C" fn _start() -> ! {
 #[cfg(not(test))]
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-    println!("{info}");
-    loop {}
+    unsafe { solas_os::drivers::vga_text::force_print(format_args!("{info}\n")) };    // The panic may have interrupted a print.
+    solas_os::hlt_loop();
 }
 
 /// Panic Handler -- Unit Test Execution
@@ -0,0 +1,383 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$                  /$$       /$$          
+// | $$__  $$                | $$      | $$          
+// | $$  \ $$ /$$   /$$  /$$$$$$$  /$$$$$$$ /$$   /$$
+// | $$$$$$$ | $$  | $$ /$$__  $$ /$$__  $$| $$  | $$
+// | $$__  $$| $$  | $$| $$  | $$| $$  | $$| $$  | $$
+// | $$  \ $$| $$  | $$| $$  | $$| $$  | $$| $$  | $$
+// | $$$$$$$/|  $$$$$$/|  $$$$$$$|  $$$$$$$|  $$$$$$$
+// |_______/  \______/  \_______/ \_______/ \____  $$
+//                                          /$$  | $$
+//                                         |  $$$$$$/
+//                                          \______/ 
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A buddy allocator, which hands out physically contiguous, naturally aligned blocks of frames from
+//! a pool reserved out of the frame allocator. Blocks are split in halves to satisfy smaller requests
+//! and merged with their buddy as soon as both halves are free again.
+//!
+
+use core::fmt;
+
+use x86_64::PhysAddr;
+use x86_64::structures::paging::PhysFrame;
+
+use crate::sync::irq_mutex::IrqMutex;
+use super::frame_allocator::{ FRAME_ALLOCATOR, FRAME_SIZE };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The highest order of block which may be allocated, being `2^MAX_ORDER` frames (2 MiB).
+pub const MAX_ORDER: usize = 9;
+
+/// The amount of block sizes that the allocator manages.
+pub const ORDERS: usize = MAX_ORDER + 1;
+
+/// The amount of maximum order blocks within the pool.
+pub const POOL_BLOCKS: usize = 4;
+
+/// The amount of frames within the pool.
+pub const POOL_FRAMES: usize = POOL_BLOCKS << MAX_ORDER;
+
+/// The amount of words required to hold a bit for every block of the lowest order.
+const BITMAP_WORDS: usize = POOL_FRAMES / u64::BITS as usize;
+
+/// The global buddy allocator.
+pub static BUDDY_ALLOCATOR: IrqMutex<BuddyAllocator> = IrqMutex::new(BuddyAllocator::new(PhysAddr::zero()));
+
+
+/*
+ * Buddy Error &
+ *      Report
+ */
+
+
+/// The reasons why a buddy allocation may fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuddyError {
+
+    /// The requested order exceeds `MAX_ORDER`.
+    InvalidOrder,
+
+    /// No block of the requested order or higher is free.
+    OutOfMemory
+}
+
+/// A snapshot of how the pool's free memory is split up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentationReport {
+    pub free_blocks:   [usize; ORDERS],    // The amount of free blocks of each order.
+    pub free_frames:   usize,
+    pub largest_order: Option<usize>       // The order of the largest free block.
+}
+
+impl FragmentationReport {
+
+    /// Gets the percentage of free memory which lies outside of the free blocks of the largest
+    /// order, ranging from zero when free memory is fully coalesced to almost one hundred when it is
+    /// scattered.
+    pub fn fragmentation(&self) -> usize {
+        match self.largest_order {
+            Some(order) => (self.free_frames - (self.free_blocks[order] << order)) * 100 / self.free_frames,
+            None        => 0
+        }
+    }
+}
+
+impl fmt::Display for FragmentationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Buddy allocator: {} KiB free, {}% fragmented", self.free_frames * FRAME_SIZE as usize / 1024, self.fragmentation())?;
+        for (order, count) in self.free_blocks.iter().enumerate() {
+            writeln!(f, "  order {:>2} ({:>5} KiB): {} free", order, (FRAME_SIZE << order) / 1024, count)?;
+        }
+        Ok(())
+    }
+}
+
+
+/*
+ * Buddy
+ *      Allocator
+ */
+
+
+/// A buddy allocator over a pool of `POOL_FRAMES` contiguous frames.
+/// # Note
+/// Free blocks are tracked with a bitmap per order rather than with lists threaded through the
+/// blocks themselves, so the allocator never has to touch the memory it manages.
+pub struct BuddyAllocator {
+    base:        PhysAddr,
+    free:        [[u64; BITMAP_WORDS]; ORDERS],    // A set bit marks a free block of that order.
+    free_frames: usize
+}
+
+impl BuddyAllocator {
+
+    /// Creates a new allocator over the pool starting at the given address, with every block in
+    /// use.
+    pub const fn new(base: PhysAddr) -> Self {
+        BuddyAllocator {
+            base,
+            free:        [[0; BITMAP_WORDS]; ORDERS],
+            free_frames: 0
+        }
+    }
+
+    /// Creates a new allocator over the pool starting at the given address, with every block free.
+    /// # Safety
+    /// The pool must be aligned to a block of the highest order, and must be unused.
+    pub unsafe fn with_free_pool(base: PhysAddr) -> Self {
+        assert!(base.is_aligned(FRAME_SIZE << MAX_ORDER), "The pool must be aligned to the largest block size");
+        
+        let mut allocator: Self = Self::new(base);
+        for block in 0..POOL_BLOCKS {
+            allocator.set_free(MAX_ORDER, block, true);
+        }
+        allocator.free_frames = POOL_FRAMES;
+        allocator
+    }
+
+    /// Gets the address at which the pool begins.
+    pub fn base(&self) -> PhysAddr {
+        self.base
+    }
+
+    /// Allocates a block of `2^order` frames, aligned to its own size.
+    pub fn allocate(&mut self, order: usize) -> Result<PhysFrame, BuddyError> {
+        if order > MAX_ORDER {
+            return Err(BuddyError::InvalidOrder);
+        }
+
+        // Take the smallest free block that fits, then split it down to the requested order.
+        let (mut current, mut block): (usize, usize) = (order..ORDERS)
+            .find_map(|order| self.find_free(order).map(|block| (order, block)))
+            .ok_or(BuddyError::OutOfMemory)?;
+        self.set_free(current, block, false);
+        while current > order {
+            current -= 1;
+            block   *= 2;
+            self.set_free(current, block + 1, true);
+        }
+
+        self.free_frames -= 1 << order;
+        Ok(PhysFrame::containing_address(self.base + ((block << order) as u64 * FRAME_SIZE)))
+    }
+
+    /// Frees a block of `2^order` frames, merging it with its buddy for as long as the buddy is
+    /// also free.
+    /// # Safety
+    /// The block must have been allocated from this allocator with the same order, and must no
+    /// longer be in use.
+    pub unsafe fn free(&mut self, frame: PhysFrame, order: usize) -> () {
+        assert!(order <= MAX_ORDER, "Order {} exceeds the highest order", order);
+        
+        assert!(frame.start_address() >= self.base, "Frame {:?} lies outside of the pool", frame);
+        
+        let mut current: usize = order;
+        let mut block:   usize = ((frame.start_address() - self.base) / FRAME_SIZE) as usize >> order;
+        assert!(block < POOL_BLOCKS << (MAX_ORDER - order), "Frame {:?} lies outside of the pool", frame);
+        
+        assert!(!self.overlaps_free(order, block), "Frame {:?} was freed twice", frame);
+        
+        while current < MAX_ORDER && self.is_free(current, block ^ 1) {
+            self.set_free(current, block ^ 1, false);
+            current += 1;
+            block   /= 2;
+        }
+        self.set_free(current, block, true);
+        self.free_frames += 1 << order;
+    }
+
+    /// Gets the amount of frames that are currently free.
+    pub fn free_frames(&self) -> usize {
+        self.free_frames
+    }
+
+    /// Reports how the pool's free memory is split up.
+    pub fn report(&self) -> FragmentationReport {
+        let mut free_blocks: [usize; ORDERS] = [0; ORDERS];
+        for (order, count) in free_blocks.iter_mut().enumerate() {
+            *count = self.free[order].iter().map(|word| word.count_ones() as usize).sum();
+        }
+
+        FragmentationReport {
+            free_blocks,
+            free_frames:   self.free_frames,
+            largest_order: (0..ORDERS).rev().find(|order| free_blocks[*order] > 0)
+        }
+    }
+
+    /// Finds the first free block of the given order.
+    fn find_free(&self, order: usize) -> Option<usize> {
+        self.free[order].iter()
+            .position(|word| *word != 0)
+            .map(|word| word * 64 + self.free[order][word].trailing_zeros() as usize)
+    }
+
+    /// Whether any part of the given block of the given order is free, either because it was freed
+    /// and merged into a larger block, or because it was split and some of its parts were freed.
+    fn overlaps_free(&self, order: usize, block: usize) -> bool {
+        let contained: bool = (order..ORDERS).any(|parent| self.is_free(parent, block >> (parent - order)));
+        let contains:  bool = (0..order).any(|child| {
+            let shift: usize = order - child;
+            (block << shift..(block + 1) << shift).any(|part| self.is_free(child, part))
+        });
+        contained || contains
+    }
+
+    /// Whether the given block of the given order is free.
+    fn is_free(&self, order: usize, block: usize) -> bool {
+        self.free[order][block / 64] & (1 << (block % 64)) != 0
+    }
+
+    /// Marks the given block of the given order as free or in use.
+    fn set_free(&mut self, order: usize, block: usize, free: bool) -> () {
+        if free {
+            self.free[order][block / 64] |= 1 << (block % 64);
+        } else {
+            self.free[order][block / 64] &= !(1 << (block % 64));
+        }
+    }
+}
+
+
+/*
+ * Global Allocator
+ *      Routines
+ */
+
+
+/// Reserves the pool of the global buddy allocator from the frame allocator.
+/// # Note
+/// This must only be called once, after the frame allocator has been initialized.
+pub fn init() -> Result<(), BuddyError> {
+    let base: PhysFrame = FRAME_ALLOCATOR.lock().allocate_contiguous(POOL_FRAMES, 1 << MAX_ORDER).ok_or(BuddyError::OutOfMemory)?;
+    *BUDDY_ALLOCATOR.lock() = unsafe { BuddyAllocator::with_free_pool(base.start_address()) };
+    Ok(())
+}
+
+/// Allocates a block of `2^order` frames from the global buddy allocator.
+pub fn allocate(order: usize) -> Result<PhysFrame, BuddyError> {
+    BUDDY_ALLOCATOR.lock().allocate(order)
+}
+
+/// Returns a block of `2^order` frames to the global buddy allocator.
+/// # Safety
+/// The block must have been allocated from the global buddy allocator with the same order, and
+/// must no longer be in use.
+pub unsafe fn free(frame: PhysFrame, order: usize) -> () {
+    BUDDY_ALLOCATOR.lock().free(frame, order);
+}
+
+/// Gets the smallest order whose blocks hold at least the given amount of bytes.
+pub fn order_for(size: usize) -> usize {
+    let frames: usize = size.div_ceil(FRAME_SIZE as usize).max(1);
+    frames.next_power_of_two().trailing_zeros() as usize
+}
+
+
+/*
+ * Buddy
+ *      Tests
+ */
+
+
+/// A pool base which is never touched, as the allocator only keeps track of addresses.
+#[cfg(test)]
+const TEST_BASE: u64 = 0x4000_0000;
+
+#[test_case]
+fn test_order_for() -> () {
+    assert_eq!(order_for(1), 0);
+    assert_eq!(order_for(4096), 0);
+    assert_eq!(order_for(4097), 1);
+    assert_eq!(order_for(2 * 1024 * 1024), MAX_ORDER);
+}
+
+#[test_case]
+fn test_allocate_every_order() -> () {
+    let mut allocator: BuddyAllocator = unsafe { BuddyAllocator::with_free_pool(PhysAddr::new(TEST_BASE)) };
+    for order in 0..ORDERS {
+        let frame: PhysFrame = allocator.allocate(order).unwrap();
+        assert!(frame.start_address().is_aligned(FRAME_SIZE << order));
+        unsafe { allocator.free(frame, order) };
+    }
+    assert_eq!(allocator.allocate(ORDERS), Err(BuddyError::InvalidOrder));
+    assert_eq!(allocator.report().free_blocks[MAX_ORDER], POOL_BLOCKS);
+}
+
+#[test_case]
+fn test_exhaust_and_coalesce() -> () {
+    const BLOCKS: usize = POOL_FRAMES >> 3;
+    
+    let mut allocator: BuddyAllocator     = unsafe { BuddyAllocator::with_free_pool(PhysAddr::new(TEST_BASE)) };
+    let mut frames:    [PhysFrame; BLOCKS] = [PhysFrame::containing_address(PhysAddr::zero()); BLOCKS];
+    for frame in frames.iter_mut() {
+        *frame = allocator.allocate(3).unwrap();
+    }
+    assert_eq!(allocator.allocate(0), Err(BuddyError::OutOfMemory));
+    assert_eq!(allocator.free_frames(), 0);
+
+    // Shuffle the blocks with a fixed xorshift sequence before freeing them.
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    for i in (1..BLOCKS).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        frames.swap(i, state as usize % (i + 1));
+    }
+    for frame in frames.iter() {
+        unsafe { allocator.free(*frame, 3) };
+    }
+
+    let report: FragmentationReport = allocator.report();
+    assert_eq!(report.free_blocks[..MAX_ORDER], [0; MAX_ORDER]);
+    assert_eq!(report.free_blocks[MAX_ORDER], POOL_BLOCKS);
+    assert_eq!(report.fragmentation(), 0);
+    
+    // Splitting off a single frame leaves the rest of its max-order block scattered.
+    let frame: PhysFrame = allocator.allocate(0).unwrap();
+    let split: usize     = (1 << MAX_ORDER) - 1;
+    assert_eq!(allocator.report().fragmentation(), split * 100 / (POOL_FRAMES - 1));
+    unsafe { allocator.free(frame, 0) };
+    assert_eq!(allocator.report().fragmentation(), 0);
+}
+
+#[test_case]
+fn test_overlapping_free_blocks() -> () {
+    let mut allocator: BuddyAllocator = unsafe { BuddyAllocator::with_free_pool(PhysAddr::new(TEST_BASE)) };
+    let     frame:     PhysFrame      = allocator.allocate(0).unwrap();
+    let     block:     usize          = ((frame.start_address() - PhysAddr::new(TEST_BASE)) / FRAME_SIZE) as usize;
+    
+    // The frame's buddy is free, so the pair may not be freed as one block.
+    assert!(!allocator.overlaps_free(0, block));
+    assert!(allocator.overlaps_free(1, block >> 1));
+    assert!(allocator.overlaps_free(MAX_ORDER, block >> MAX_ORDER));
+
+    // Once freed, the frame is merged back into its max-order block.
+    unsafe { allocator.free(frame, 0) };
+    assert!(allocator.overlaps_free(0, block));
+}
+
+#[test_case]
+fn test_global_pool_writable() -> () {
+    let frame: PhysFrame = allocate(1).unwrap();
+    let block: *mut u64  = super::paging::phys_to_virt(frame.start_address()).as_mut_ptr();
+    unsafe {
+        block.add(1023).write_volatile(0xb0dd1e5);
+        assert_eq!(block.add(1023).read_volatile(), 0xb0dd1e5);
+        free(frame, 1);
+    }
+}
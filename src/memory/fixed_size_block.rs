@@ -0,0 +1,185 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$$ /$$                           /$$        /$$$$$$  /$$                           /$$$$$$$  /$$                     /$$      
+// | $$_____/|__/                          | $$       /$$__  $$|__/                          | $$__  $$| $$                    | $$      
+// | $$       /$$ /$$   /$$  /$$$$$$   /$$$$$$$      | $$  \__/ /$$ /$$$$$$$$  /$$$$$$       | $$  \ $$| $$  /$$$$$$   /$$$$$$$| $$   /$$
+// | $$$$$   | $$|  $$ /$$/ /$$__  $$ /$$__  $$      |  $$$$$$ | $$|____ /$$/ /$$__  $$      | $$$$$$$ | $$ /$$__  $$ /$$_____/| $$  /$$/
+// | $$__/   | $$ \  $$$$/ | $$$$$$$$| $$  | $$       \____  $$| $$   /$$$$/ | $$$$$$$$      | $$__  $$| $$| $$  \ $$| $$      | $$$$$$/ 
+// | $$      | $$  >$$  $$ | $$_____/| $$  | $$       /$$  \ $$| $$  /$$__/  | $$_____/      | $$  \ $$| $$| $$  | $$| $$      | $$_  $$ 
+// | $$      | $$ /$$/\  $$|  $$$$$$$|  $$$$$$$      |  $$$$$$/| $$ /$$$$$$$$|  $$$$$$$      | $$$$$$$/| $$|  $$$$$$/|  $$$$$$$| $$ \  $$
+// |__/      |__/|__/  \__/ \_______/ \_______/       \______/ |__/|________/ \_______/      |_______/ |__/ \______/  \_______/|__/  \__/
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A heap allocator which serves small allocations from power-of-two sized blocks kept on per-size
+//! free lists, and falls back to a linked list allocator for anything larger.
+//!
+
+use core::alloc::Layout;
+use core::ptr::{ self, NonNull };
+use core::mem;
+
+use linked_list_allocator::Heap;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The size classes of the blocks which are kept on free lists.
+/// # Note
+/// Each size is also used as the block's alignment, so every size must be a power of two. The
+/// smallest size must also be able to hold a `ListNode`.
+pub const BLOCK_SIZES: &[usize] = &[8, 16, 32, 64, 128, 256, 512, 1024, 2048];
+
+
+/*
+ * Fixed Size Block
+ *      Allocator
+ */
+
+
+/// A free block, which points to the next free block of the same size.
+struct ListNode {
+    next: Option<&'static mut ListNode>
+}
+
+/// An allocator which rounds small allocations up to the nearest size class, and keeps freed blocks
+/// on a free list for that class so they may be handed out again without searching.
+/// # Note
+/// Blocks are carved out of the fallback allocator on demand, and are never returned to it.
+pub struct FixedSizeBlockAllocator {
+    list_heads: [Option<&'static mut ListNode>; BLOCK_SIZES.len()],
+    fallback:   Heap,
+    cached:     usize    // The amount of bytes sitting in the free lists.
+}
+
+impl FixedSizeBlockAllocator {
+
+    /// Creates a new, empty allocator which fails every allocation until it is initialized.
+    pub const fn new() -> Self {
+        const EMPTY: Option<&'static mut ListNode> = None;
+        FixedSizeBlockAllocator {
+            list_heads: [EMPTY; BLOCK_SIZES.len()],
+            fallback:   Heap::empty(),
+            cached:     0
+        }
+    }
+
+    /// Hands the given region of memory over to the allocator.
+    /// # Safety
+    /// The region must be mapped, writable and unused, and this must only be called once.
+    pub unsafe fn init(&mut self, start: *mut u8, size: usize) -> () {
+        self.fallback.init(start, size);
+    }
+
+    /// Allocates a block of memory fitting the given layout, or returns a null pointer if there is
+    /// not enough memory left.
+    pub fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        match size_class(&layout) {
+            Some(index) => match self.list_heads[index].take() {
+                Some(node) => {
+                    self.list_heads[index]  = node.next.take();
+                    self.cached            -= BLOCK_SIZES[index];
+                    node as *mut ListNode as *mut u8
+                },
+                None => {
+                    let block: Layout = Layout::from_size_align(BLOCK_SIZES[index], BLOCK_SIZES[index]).unwrap();
+                    self.allocate_fallback(block)
+                }
+            },
+            None => self.allocate_fallback(layout)
+        }
+    }
+
+    /// Frees a block of memory which was allocated with the given layout.
+    /// # Safety
+    /// The block must have been allocated by this allocator with the same layout, and must no
+    /// longer be in use.
+    pub unsafe fn deallocate(&mut self, ptr: *mut u8, layout: Layout) -> () {
+        match size_class(&layout) {
+            Some(index) => {
+                let node: *mut ListNode = ptr as *mut ListNode;
+                node.write(ListNode { next: self.list_heads[index].take() });
+                
+                self.list_heads[index]  = Some(&mut *node);
+                self.cached            += BLOCK_SIZES[index];
+            },
+            None => self.fallback.deallocate(NonNull::new_unchecked(ptr), layout)
+        }
+    }
+
+    /// Gets the amount of bytes that are currently allocated, including the padding of blocks.
+    pub fn used(&self) -> usize {
+        self.fallback.used() - self.cached
+    }
+
+    /// Gets the amount of bytes that are currently free, including those kept on the free lists.
+    pub fn free(&self) -> usize {
+        self.fallback.free() + self.cached
+    }
+
+    /// Allocates straight from the fallback allocator.
+    fn allocate_fallback(&mut self, layout: Layout) -> *mut u8 {
+        self.fallback.allocate_first_fit(layout).map_or(ptr::null_mut(), |block| block.as_ptr())
+    }
+}
+
+impl Default for FixedSizeBlockAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the index of the smallest size class that fits the given layout, if any does.
+fn size_class(layout: &Layout) -> Option<usize> {
+    let required: usize = layout.size().max(layout.align()).max(mem::size_of::<ListNode>());
+    BLOCK_SIZES.iter().position(|&size| size >= required)
+}
+
+
+/*
+ * Fixed Size Block
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_size_classes() -> () {
+    assert_eq!(size_class(&Layout::from_size_align(1, 1).unwrap()), Some(0));
+    assert_eq!(size_class(&Layout::from_size_align(9, 1).unwrap()), Some(1));
+    assert_eq!(size_class(&Layout::from_size_align(8, 64).unwrap()), Some(3));
+    assert_eq!(size_class(&Layout::from_size_align(2048, 8).unwrap()), Some(BLOCK_SIZES.len() - 1));
+    assert_eq!(size_class(&Layout::from_size_align(2049, 8).unwrap()), None);
+}
+
+#[test_case]
+fn test_blocks_reused() -> () {
+    let mut memory:    [u64; 1024]             = [0; 1024];
+    let mut allocator: FixedSizeBlockAllocator = FixedSizeBlockAllocator::new();
+    unsafe { allocator.init(memory.as_mut_ptr() as *mut u8, mem::size_of_val(&memory)) };
+
+    let small: Layout  = Layout::from_size_align(24, 8).unwrap();
+    let first: *mut u8 = allocator.allocate(small);
+    assert!(!first.is_null());
+    assert_eq!(allocator.used(), 32);
+    
+    unsafe { allocator.deallocate(first, small) };
+    assert_eq!(allocator.used(), 0);
+    assert_eq!(allocator.allocate(small), first);
+
+    // Large allocations bypass the free lists entirely.
+    let large: Layout  = Layout::from_size_align(4096, 8).unwrap();
+    let block: *mut u8 = allocator.allocate(large);
+    assert!(!block.is_null());
+    assert_eq!(allocator.used(), 32 + 4096);
+    unsafe { allocator.deallocate(block, large) };
+    assert_eq!(allocator.used(), 32);
+}
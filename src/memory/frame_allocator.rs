@@ -0,0 +1,283 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$$                                                /$$$$$$  /$$ /$$                                 /$$                        
+// | $$_____/                                               /$$__  $$| $$| $$                                | $$                        
+// | $$     /$$$$$$  /$$$$$$  /$$$$$$/$$$$   /$$$$$$       | $$  \ $$| $$| $$  /$$$$$$   /$$$$$$$  /$$$$$$  /$$$$$$    /$$$$$$   /$$$$$$ 
+// | $$$$$ /$$__  $$|____  $$| $$_  $$_  $$ /$$__  $$      | $$$$$$$$| $$| $$ /$$__  $$ /$$_____/ |____  $$|_  $$_/   /$$__  $$ /$$__  $$
+// | $$__/| $$  \__/ /$$$$$$$| $$ \ $$ \ $$| $$$$$$$$      | $$__  $$| $$| $$| $$  \ $$| $$        /$$$$$$$  | $$    | $$  \ $$| $$  \__/
+// | $$   | $$      /$$__  $$| $$ | $$ | $$| $$_____/      | $$  | $$| $$| $$| $$  | $$| $$       /$$__  $$  | $$ /$$| $$  | $$| $$      
+// | $$   | $$     |  $$$$$$$| $$ | $$ | $$|  $$$$$$$      | $$  | $$| $$| $$|  $$$$$$/|  $$$$$$$|  $$$$$$$  |  $$$$/|  $$$$$$/| $$      
+// |__/   |__/      \_______/|__/ |__/ |__/ \_______/      |__/  |__/|__/|__/ \______/  \_______/ \_______/   \___/   \______/ |__/      
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A bitmap-based physical frame allocator, which hands out 4 KiB frames from the usable regions of
+//! the bootloader's memory map and takes them back once they are freed.
+//!
+
+use x86_64::PhysAddr;
+use x86_64::structures::paging::{ FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB, PageSize };
+
+use crate::sync::irq_mutex::IrqMutex;
+use super::map::{ MemoryMap, RegionKind };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The size of a single frame, in bytes.
+pub const FRAME_SIZE: u64 = Size4KiB::SIZE;
+
+/// The highest amount of physical memory that the allocator can track. Usable memory above this is
+/// ignored.
+pub const MAX_PHYSICAL_MEMORY: u64 = 4 * 1024 * 1024 * 1024;
+
+/// The amount of frames that the allocator can track, along with the amount of words required to
+/// hold a bit for each of them.
+const MAX_FRAMES:   usize = (MAX_PHYSICAL_MEMORY / FRAME_SIZE) as usize;
+const BITMAP_WORDS: usize = MAX_FRAMES / u64::BITS as usize;
+
+/// The global physical frame allocator.
+pub static FRAME_ALLOCATOR: IrqMutex<BitmapFrameAllocator> = IrqMutex::new(BitmapFrameAllocator::new());
+
+
+/*
+ * Bitmap Frame
+ *      Allocator
+ */
+
+
+/// A physical frame allocator which tracks every frame with a single bit, set whilst the frame is
+/// free.
+/// # Note
+/// As the bitmap is zeroed, every frame starts out as in use until usable regions are added, which
+/// keeps reserved memory and holes in the memory map from ever being handed out.
+pub struct BitmapFrameAllocator {
+    bitmap:      [u64; BITMAP_WORDS],
+    total:       usize,    // The amount of frames that were added as usable.
+    free:        usize,    // The amount of those frames which are currently free.
+    search_hint: usize     // The word index to resume searching for a free frame from.
+}
+
+impl BitmapFrameAllocator {
+
+    /// Creates a new allocator with no usable frames.
+    pub const fn new() -> Self {
+        BitmapFrameAllocator {
+            bitmap:      [0; BITMAP_WORDS],
+            total:       0,
+            free:        0,
+            search_hint: 0
+        }
+    }
+
+    /// Adds every frame lying entirely within a usable region of the memory map to the allocator.
+    pub fn add_usable_regions(&mut self, map: &MemoryMap) -> () {
+        for region in map.regions_of(RegionKind::Usable) {
+            let start: u64 = region.start.align_up(FRAME_SIZE).as_u64() / FRAME_SIZE;
+            let end:   u64 = region.end.align_down(FRAME_SIZE).as_u64() / FRAME_SIZE;
+            for frame in start..end.min(MAX_FRAMES as u64) {
+                if !self.is_free(frame as usize) {
+                    self.set_free(frame as usize, true);
+                    self.total += 1;
+                    self.free  += 1;
+                }
+            }
+        }
+    }
+
+    /// Gets the amount of frames the allocator manages.
+    pub fn total_frames(&self) -> usize {
+        self.total
+    }
+
+    /// Gets the amount of frames that are currently free.
+    pub fn free_frames(&self) -> usize {
+        self.free
+    }
+
+    /// Gets the amount of frames that are currently allocated.
+    pub fn used_frames(&self) -> usize {
+        self.total - self.free
+    }
+
+    /// Allocates a physically contiguous run of frames, whose first frame number is a multiple of
+    /// the given alignment, returning the first frame of the run.
+    /// # Note
+    /// This searches the whole bitmap, so it is much slower than allocating single frames and is
+    /// best reserved for carving out large pools up front.
+    pub fn allocate_contiguous(&mut self, count: usize, align: usize) -> Option<PhysFrame> {
+        assert!(count > 0 && align.is_power_of_two(), "Contiguous runs must be non-empty and aligned to a power of two");
+        
+        let start: usize = (0..MAX_FRAMES.saturating_sub(count - 1))
+            .step_by(align)
+            .find(|start| (*start..*start + count).all(|frame| self.is_free(frame)))?;
+        for frame in start..start + count {
+            self.set_free(frame, false);
+        }
+        self.free -= count;
+        Some(Self::frame(start))
+    }
+
+    /// Whether the frame with the given number is currently free.
+    pub fn is_frame_free(&self, frame: PhysFrame) -> bool {
+        let number: u64 = frame.start_address().as_u64() / FRAME_SIZE;
+        number < MAX_FRAMES as u64 && self.is_free(number as usize)
+    }
+
+    /// Whether the frame with the given number is free.
+    fn is_free(&self, frame: usize) -> bool {
+        self.bitmap[frame / 64] & (1 << (frame % 64)) != 0
+    }
+
+    /// Marks the frame with the given number as free or in use.
+    fn set_free(&mut self, frame: usize, free: bool) -> () {
+        if free {
+            self.bitmap[frame / 64] |= 1 << (frame % 64);
+        } else {
+            self.bitmap[frame / 64] &= !(1 << (frame % 64));
+        }
+    }
+
+    /// Converts a frame number into its frame.
+    fn frame(number: usize) -> PhysFrame {
+        PhysFrame::containing_address(PhysAddr::new(number as u64 * FRAME_SIZE))
+    }
+}
+
+unsafe impl FrameAllocator<Size4KiB> for BitmapFrameAllocator {
+
+    /// Allocates the lowest free frame at or after the last word a frame was found in.
+    fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        if self.free == 0 {
+            return None;
+        }
+
+        let word: usize = (self.search_hint..BITMAP_WORDS)
+            .chain(0..self.search_hint)
+            .find(|word| self.bitmap[*word] != 0)?;
+        let number: usize = word * 64 + self.bitmap[word].trailing_zeros() as usize;
+        
+        self.set_free(number, false);
+        self.free        -= 1;
+        self.search_hint  = word;
+        Some(Self::frame(number))
+    }
+}
+
+impl FrameDeallocator<Size4KiB> for BitmapFrameAllocator {
+
+    /// Returns a frame to the allocator.
+    /// # Panics
+    /// Panics if the frame is already free, as this indicates a double free.
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) -> () {
+        let number: usize = (frame.start_address().as_u64() / FRAME_SIZE) as usize;
+        assert!(number < MAX_FRAMES, "Frame {:?} lies outside of the tracked physical memory", frame);
+        assert!(!self.is_free(number), "Frame {:?} was freed twice", frame);
+        
+        self.set_free(number, true);
+        self.free        += 1;
+        self.search_hint  = self.search_hint.min(number / 64);
+    }
+}
+
+impl Default for BitmapFrameAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*
+ * Global Allocator
+ *      Routines
+ */
+
+
+/// Adds the usable regions of the memory map to the global frame allocator.
+pub fn init(map: &MemoryMap) -> () {
+    FRAME_ALLOCATOR.lock().add_usable_regions(map);
+}
+
+/// Allocates a single frame from the global frame allocator.
+pub fn allocate_frame() -> Option<PhysFrame> {
+    FRAME_ALLOCATOR.lock().allocate_frame()
+}
+
+/// Returns a single frame to the global frame allocator.
+/// # Safety
+/// The frame must have been allocated from the global frame allocator, and must no longer be in
+/// use.
+pub unsafe fn deallocate_frame(frame: PhysFrame) -> () {
+    FRAME_ALLOCATOR.lock().deallocate_frame(frame);
+}
+
+
+/*
+ * Frame Allocator
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_frames_reported() -> () {
+    let allocator: crate::sync::irq_mutex::IrqMutexGuard<BitmapFrameAllocator> = FRAME_ALLOCATOR.lock();
+    assert!(allocator.total_frames() > 0);
+    assert!(allocator.free_frames() <= allocator.total_frames());
+}
+
+#[test_case]
+fn test_allocate_and_free_thousands() -> () {
+    const BATCH: usize = 2048;
+    
+    let baseline: usize = FRAME_ALLOCATOR.lock().free_frames();
+    for _ in 0..4 {
+        let mut frames: [u64; BATCH] = [0; BATCH];
+        for frame in frames.iter_mut() {
+            *frame = allocate_frame().expect("Ran out of frames").start_address().as_u64();
+        }
+        assert_eq!(FRAME_ALLOCATOR.lock().free_frames(), baseline - BATCH);
+
+        // Every frame handed out must be distinct and aligned.
+        frames.sort_unstable();
+        assert!(frames.windows(2).all(|pair| pair[0] != pair[1]));
+        assert!(frames.iter().all(|frame| frame % FRAME_SIZE == 0));
+
+        for frame in frames {
+            unsafe { deallocate_frame(PhysFrame::containing_address(PhysAddr::new(frame))) };
+        }
+        assert_eq!(FRAME_ALLOCATOR.lock().free_frames(), baseline);
+    }
+}
+
+#[test_case]
+fn test_allocate_contiguous() -> () {
+    let baseline: usize     = FRAME_ALLOCATOR.lock().free_frames();
+    let first:    PhysFrame = FRAME_ALLOCATOR.lock().allocate_contiguous(16, 16).expect("No contiguous run was free");
+    assert_eq!(first.start_address().as_u64() % (16 * FRAME_SIZE), 0);
+    assert_eq!(FRAME_ALLOCATOR.lock().free_frames(), baseline - 16);
+
+    for frame in PhysFrame::range(first, first + 16) {
+        unsafe { deallocate_frame(frame) };
+    }
+    assert_eq!(FRAME_ALLOCATOR.lock().free_frames(), baseline);
+}
+
+#[test_case]
+fn test_freed_frame_reused() -> () {
+    let frame: PhysFrame = allocate_frame().unwrap();
+    assert!(!FRAME_ALLOCATOR.lock().is_frame_free(frame));
+    
+    unsafe { deallocate_frame(frame) };
+    assert!(FRAME_ALLOCATOR.lock().is_frame_free(frame));
+    assert_eq!(allocate_frame(), Some(frame));
+    unsafe { deallocate_frame(frame) };
+}
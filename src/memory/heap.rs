@@ -0,0 +1,189 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                              
+// | $$  | $$                              
+// | $$  | $$  /$$$$$$   /$$$$$$   /$$$$$$ 
+// | $$$$$$$$ /$$__  $$ |____  $$ /$$__  $$
+// | $$__  $$| $$$$$$$$  /$$$$$$$| $$  \ $$
+// | $$  | $$| $$_____/ /$$__  $$| $$  | $$
+// | $$  | $$|  $$$$$$$|  $$$$$$$| $$$$$$$/
+// |__/  |__/ \_______/ \_______/| $$____/ 
+//                               | $$      
+//                               | $$      
+//                               |__/      
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! The kernel heap, which lives in a dedicated region of virtual memory and backs the `alloc`
+//! crate's `Box`, `Vec`, `String` and friends.
+//!
+//! The allocation strategy is chosen at compile time: the `fixed_size_block` feature serves small
+//! allocations from per-size free lists, whereas disabling it serves every allocation from a linked
+//! list of free regions.
+//!
+
+use core::alloc::{ GlobalAlloc, Layout };
+#[cfg(not(feature = "fixed_size_block"))]
+use core::ptr::{ self, NonNull };
+
+use x86_64::VirtAddr;
+use x86_64::structures::paging::{ Page, PageTableFlags };
+#[cfg(not(feature = "fixed_size_block"))]
+use linked_list_allocator::Heap;
+
+use crate::{ println, serial_println };
+use crate::sync::irq_mutex::IrqMutex;
+use super::paging::{ self, PagingError };
+#[cfg(feature = "fixed_size_block")]
+use super::fixed_size_block::FixedSizeBlockAllocator;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The virtual address at which the kernel heap begins.
+pub const HEAP_START: u64 = 0x4444_4444_0000;
+
+/// The size of the kernel heap, in bytes.
+pub const HEAP_SIZE: usize = 1024 * 1024;
+
+/// The name of the allocation strategy the heap was built with.
+#[cfg(feature = "fixed_size_block")]
+pub const STRATEGY: &str = "fixed size block";
+#[cfg(not(feature = "fixed_size_block"))]
+pub const STRATEGY: &str = "linked list";
+
+/// The allocator behind every heap allocation made by the kernel.
+#[global_allocator]
+static ALLOCATOR: KernelHeap = KernelHeap::new();
+
+
+/*
+ * Kernel Heap
+ *      Allocator
+ */
+
+
+/// The allocator which the kernel heap delegates to.
+#[cfg(feature = "fixed_size_block")]
+type Strategy = FixedSizeBlockAllocator;
+#[cfg(not(feature = "fixed_size_block"))]
+type Strategy = Heap;
+
+/// The kernel's global allocator, which serves allocations with the selected strategy.
+/// # Note
+/// The heap is guarded by an `IrqMutex`, so interrupt handlers may allocate without deadlocking
+/// against the code they interrupted.
+pub struct KernelHeap {
+    heap: IrqMutex<Strategy>
+}
+
+impl KernelHeap {
+
+    /// Creates a new, empty heap which fails every allocation until it is initialized.
+    pub const fn new() -> Self {
+        #[cfg(feature = "fixed_size_block")]
+        let strategy: Strategy = FixedSizeBlockAllocator::new();
+        #[cfg(not(feature = "fixed_size_block"))]
+        let strategy: Strategy = Heap::empty();
+
+        KernelHeap {
+            heap: IrqMutex::new(strategy)
+        }
+    }
+
+    /// Hands the given region of memory over to the heap.
+    /// # Safety
+    /// The region must be mapped, writable and unused, and this must only be called once.
+    pub unsafe fn init(&self, start: *mut u8, size: usize) -> () {
+        self.heap.lock().init(start, size);
+    }
+
+    /// Gets the amount of bytes that are currently allocated.
+    pub fn used(&self) -> usize {
+        self.heap.lock().used()
+    }
+
+    /// Gets the amount of bytes that are currently free.
+    pub fn free(&self) -> usize {
+        self.heap.lock().free()
+    }
+}
+
+#[cfg(feature = "fixed_size_block")]
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.heap.lock().allocate(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) -> () {
+        self.heap.lock().deallocate(ptr, layout);
+    }
+}
+
+#[cfg(not(feature = "fixed_size_block"))]
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.heap.lock().allocate_first_fit(layout).map_or(ptr::null_mut(), |block| block.as_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) -> () {
+        self.heap.lock().deallocate(NonNull::new_unchecked(ptr), layout);
+    }
+}
+
+impl Default for KernelHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*
+ * Initialization
+ *      Routines
+ */
+
+
+/// Maps the heap's region of virtual memory to fresh frames and hands it over to the global
+/// allocator.
+/// # Note
+/// This must only be called once, after paging has been initialized.
+pub fn init() -> Result<(), PagingError> {
+    let start: Page = Page::containing_address(VirtAddr::new(HEAP_START));
+    let end:   Page = Page::containing_address(VirtAddr::new(HEAP_START + HEAP_SIZE as u64 - 1));
+    let flags: PageTableFlags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    for page in Page::range_inclusive(start, end) {
+        paging::map_new(page, flags)?;
+    }
+
+    unsafe { ALLOCATOR.init(HEAP_START as *mut u8, HEAP_SIZE) };
+    Ok(())
+}
+
+/// Gets the amount of heap memory that is currently allocated, in bytes.
+pub fn used() -> usize {
+    ALLOCATOR.used()
+}
+
+/// Gets the amount of heap memory that is currently free, in bytes.
+pub fn free() -> usize {
+    ALLOCATOR.free()
+}
+
+/// Reports an allocation which the heap could not satisfy, and panics.
+#[alloc_error_handler]
+fn alloc_error_handler(layout: Layout) -> ! {
+    let (used, free): (usize, usize) = (used(), free());
+    println!("ALLOCATION ERROR: {} bytes aligned to {} ({} bytes used, {} bytes free)", layout.size(), layout.align(), used, free);
+    serial_println!("ALLOCATION ERROR: {} bytes aligned to {} ({} bytes used, {} bytes free)", layout.size(), layout.align(), used, free);
+    panic!("Failed to allocate {:?}", layout);
+}
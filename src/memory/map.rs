@@ -0,0 +1,216 @@
+//===================================================================================================================================================================================//
+//
+//  /$$      /$$                                                             /$$      /$$                    
+// | $$$    /$$$                                                            | $$$    /$$$                    
+// | $$$$  /$$$$  /$$$$$$  /$$$$$$/$$$$   /$$$$$$   /$$$$$$  /$$   /$$      | $$$$  /$$$$  /$$$$$$   /$$$$$$ 
+// | $$ $$/$$ $$ /$$__  $$| $$_  $$_  $$ /$$__  $$ /$$__  $$| $$  | $$      | $$ $$/$$ $$ |____  $$ /$$__  $$
+// | $$  $$$| $$| $$$$$$$$| $$ \ $$ \ $$| $$  \ $$| $$  \__/| $$  | $$      | $$  $$$| $$  /$$$$$$$| $$  \ $$
+// | $$\  $ | $$| $$_____/| $$ | $$ | $$| $$  | $$| $$      | $$  | $$      | $$\  $ | $$ /$$__  $$| $$  | $$
+// | $$ \/  | $$|  $$$$$$$| $$ | $$ | $$|  $$$$$$/| $$      |  $$$$$$$      | $$ \/  | $$|  $$$$$$$| $$$$$$$/
+// |__/     |__/ \_______/|__/ |__/ |__/ \______/ |__/       \____  $$      |__/     |__/ \_______/| $$____/ 
+//                                                           /$$  | $$                             | $$      
+//                                                          |  $$$$$$/                             | $$      
+//                                                           \______/                              |__/      
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Provides a view over the physical memory map handed to the kernel by the bootloader, classifying
+//! each region by what it may be used for.
+//!
+
+use core::fmt;
+
+use bootloader::bootinfo::{ MemoryMap as BootMemoryMap, MemoryRegion, MemoryRegionType };
+use x86_64::PhysAddr;
+use spin::Once;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The memory map the kernel was booted with, once it has been recorded.
+static MEMORY_MAP: Once<MemoryMap> = Once::new();
+
+
+/*
+ * Region
+ *      Classification
+ */
+
+
+/// Broadly classifies what a region of physical memory may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+
+    /// Free memory which the kernel may allocate from.
+    Usable,
+
+    /// Memory which is reserved by the firmware or hardware, or is otherwise unusable.
+    Reserved,
+
+    /// Memory occupied by the kernel's image and its boot stack.
+    Kernel,
+
+    /// Memory occupied by the bootloader, the page tables it set up, or the boot information.
+    Bootloader
+}
+
+impl RegionKind {
+
+    /// Every region kind, in order.
+    pub const ALL: [Self; 4] = [Self::Usable, Self::Reserved, Self::Kernel, Self::Bootloader];
+
+    /// Classifies a region type reported by the bootloader.
+    pub fn classify(region_type: MemoryRegionType) -> Self {
+        match region_type {
+            MemoryRegionType::Usable                                    => Self::Usable,
+            MemoryRegionType::Kernel | MemoryRegionType::KernelStack    => Self::Kernel,
+            MemoryRegionType::PageTable | MemoryRegionType::Bootloader
+            | MemoryRegionType::BootInfo | MemoryRegionType::Package    => Self::Bootloader,
+            _                                                           => Self::Reserved
+        }
+    }
+
+    /// Gets the human-readable name of this region kind.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Usable     => "usable",
+            Self::Reserved   => "reserved",
+            Self::Kernel     => "kernel",
+            Self::Bootloader => "bootloader"
+        }
+    }
+}
+
+/// A single contiguous region of physical memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+
+    /// The first address of the region.
+    pub start: PhysAddr,
+
+    /// The address directly after the last address of the region.
+    pub end: PhysAddr,
+
+    /// What the region may be used for.
+    pub kind: RegionKind,
+
+    /// The exact region type reported by the bootloader.
+    pub region_type: MemoryRegionType
+}
+
+impl Region {
+
+    /// Gets the size of the region in bytes.
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+impl From<&MemoryRegion> for Region {
+    fn from(region: &MemoryRegion) -> Self {
+        Region {
+            start:       PhysAddr::new(region.range.start_addr()),
+            end:         PhysAddr::new(region.range.end_addr()),
+            kind:        RegionKind::classify(region.region_type),
+            region_type: region.region_type
+        }
+    }
+}
+
+
+/*
+ * Memory
+ *      Map
+ */
+
+
+/// A read-only view over the physical memory map reported by the bootloader.
+#[derive(Clone, Copy)]
+pub struct MemoryMap {
+    map: &'static BootMemoryMap
+}
+
+impl MemoryMap {
+
+    /// Creates a view over the memory map reported by the bootloader.
+    pub fn new(map: &'static BootMemoryMap) -> Self {
+        MemoryMap { map }
+    }
+
+    /// Iterates over every region in the map, in ascending address order.
+    pub fn regions(&self) -> impl Iterator<Item = Region> + 'static {
+        self.map.iter().map(Region::from)
+    }
+
+    /// Iterates over every region of the given kind.
+    pub fn regions_of(&self, kind: RegionKind) -> impl Iterator<Item = Region> + 'static {
+        self.regions().filter(move |region| region.kind == kind)
+    }
+
+    /// Gets the total amount of bytes held by regions of the given kind.
+    pub fn total(&self, kind: RegionKind) -> u64 {
+        self.regions_of(kind).map(|region| region.size()).sum()
+    }
+
+    /// Gets the address directly after the highest region in the map.
+    pub fn end(&self) -> PhysAddr {
+        self.regions().map(|region| region.end).max().unwrap_or(PhysAddr::zero())
+    }
+}
+
+impl fmt::Display for MemoryMap {
+
+    /// Formats a summary of the amount of memory held by each kind of region.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Physical Memory:")?;
+        for kind in RegionKind::ALL {
+            write!(f, " {} KiB {},", self.total(kind) / 1024, kind.name())?;
+        }
+        write!(f, " across {} regions", self.map.len())
+    }
+}
+
+/// Records the memory map the kernel was booted with.
+/// # Note
+/// Only the first map recorded is kept.
+pub fn init(map: &'static BootMemoryMap) -> &'static MemoryMap {
+    MEMORY_MAP.call_once(|| MemoryMap::new(map))
+}
+
+/// Gets the memory map the kernel was booted with, if it has been recorded.
+pub fn memory_map() -> Option<&'static MemoryMap> {
+    MEMORY_MAP.r#try()
+}
+
+
+/*
+ * Memory Map
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_usable_memory_reported() -> () {
+    let map: &MemoryMap = memory_map().expect("The memory map was not recorded");
+    assert!(map.total(RegionKind::Usable) > 0);
+    assert!(map.total(RegionKind::Kernel) > 0);
+}
+
+#[test_case]
+fn test_regions_do_not_overlap() -> () {
+    let map: &MemoryMap = memory_map().expect("The memory map was not recorded");
+    let mut last_end: PhysAddr = PhysAddr::zero();
+    for region in map.regions() {
+        assert!(region.start >= last_end, "Region at {:?} overlaps its predecessor", region.start);
+        last_end = region.end;
+    }
+}
@@ -0,0 +1,25 @@
+
+pub mod map;
+pub mod frame_allocator;
+pub mod paging;
+pub mod heap;
+pub mod fixed_size_block;
+pub mod slab;
+pub mod buddy;
+pub mod stack;
+
+pub use map::MemoryMap;
+
+use bootloader::BootInfo;
+use x86_64::VirtAddr;
+
+
+/// Initializes every memory subsystem from the memory map and the physical memory mapping that the
+/// bootloader handed over.
+pub fn init(boot_info: &'static BootInfo) -> () {
+    let memory_map: &MemoryMap = map::init(&boot_info.memory_map);
+    frame_allocator::init(memory_map);
+    buddy::init().expect("Failed to reserve the buddy allocator's pool");
+    unsafe { paging::init(VirtAddr::new(boot_info.physical_memory_offset)) };
+    heap::init().expect("Failed to map the kernel heap");
+}
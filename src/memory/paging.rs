@@ -0,0 +1,327 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$                     /$$                    
+// | $$__  $$                   |__/                    
+// | $$  \ $$ /$$$$$$   /$$$$$$  /$$ /$$$$$$$   /$$$$$$ 
+// | $$$$$$$/|____  $$ /$$__  $$| $$| $$__  $$ /$$__  $$
+// | $$____/  /$$$$$$$| $$  \ $$| $$| $$  \ $$| $$  \ $$
+// | $$      /$$__  $$| $$  | $$| $$| $$  | $$| $$  | $$
+// | $$     |  $$$$$$$|  $$$$$$$| $$| $$  | $$|  $$$$$$$
+// |__/      \_______/ \____  $$|__/|__/  |__/ \____  $$
+//                     /$$  \ $$               /$$  \ $$
+//                    |  $$$$$$/              |  $$$$$$/
+//                     \______/                \______/ 
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Manages the active page tables, through which pages of virtual memory may be mapped to, and
+//! unmapped from, physical frames.
+//!
+//! The bootloader maps all of physical memory at a fixed offset, which is what allows the page
+//! tables themselves to be accessed and modified.
+//!
+
+use core::fmt;
+
+use x86_64::{ PhysAddr, VirtAddr };
+use x86_64::registers::control::Cr3;
+use x86_64::structures::paging::{ Mapper, OffsetPageTable, Page, PageTable, PageTableFlags, PageTableIndex, PhysFrame, Size4KiB, Translate };
+use x86_64::structures::paging::mapper::{ FlagUpdateError, MapToError, TranslateResult, UnmapError };
+use spin::Once;
+
+use crate::sync::irq_mutex::{ IrqMutex, IrqMutexGuard };
+use super::frame_allocator::{ self, BitmapFrameAllocator, FRAME_ALLOCATOR };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The virtual address at which the bootloader mapped all of physical memory.
+static PHYSICAL_MEMORY_OFFSET: Once<VirtAddr> = Once::new();
+
+/// The mapper over the active level 4 page table.
+static MAPPER: Once<IrqMutex<OffsetPageTable<'static>>> = Once::new();
+
+
+/*
+ * Paging
+ *      Error
+ */
+
+
+/// The reasons why a paging operation may fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagingError {
+
+    /// A frame for an intermediate page table could not be allocated.
+    FrameAllocationFailed,
+
+    /// The page is already mapped to the given frame.
+    AlreadyMapped(PhysFrame),
+
+    /// The page is not mapped.
+    NotMapped,
+
+    /// One of the page's parent entries maps a huge page, which the page would lie within.
+    HugePageConflict,
+
+    /// The page is mapped to an invalid physical address.
+    InvalidFrameAddress(PhysAddr)
+}
+
+impl From<MapToError<Size4KiB>> for PagingError {
+    fn from(error: MapToError<Size4KiB>) -> Self {
+        match error {
+            MapToError::FrameAllocationFailed    => Self::FrameAllocationFailed,
+            MapToError::ParentEntryHugePage      => Self::HugePageConflict,
+            MapToError::PageAlreadyMapped(frame) => Self::AlreadyMapped(frame)
+        }
+    }
+}
+
+impl From<UnmapError> for PagingError {
+    fn from(error: UnmapError) -> Self {
+        match error {
+            UnmapError::ParentEntryHugePage          => Self::HugePageConflict,
+            UnmapError::PageNotMapped                => Self::NotMapped,
+            UnmapError::InvalidFrameAddress(address) => Self::InvalidFrameAddress(address)
+        }
+    }
+}
+
+impl From<FlagUpdateError> for PagingError {
+    fn from(error: FlagUpdateError) -> Self {
+        match error {
+            FlagUpdateError::PageNotMapped       => Self::NotMapped,
+            FlagUpdateError::ParentEntryHugePage => Self::HugePageConflict
+        }
+    }
+}
+
+impl fmt::Display for PagingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FrameAllocationFailed        => write!(f, "no frame could be allocated for a page table"),
+            Self::AlreadyMapped(frame)         => write!(f, "the page is already mapped to {:?}", frame),
+            Self::NotMapped                    => write!(f, "the page is not mapped"),
+            Self::HugePageConflict             => write!(f, "the page lies within a huge page"),
+            Self::InvalidFrameAddress(address) => write!(f, "the page is mapped to the invalid address {:?}", address)
+        }
+    }
+}
+
+
+/*
+ * Initialization
+ *      Routines
+ */
+
+
+/// Records where the bootloader mapped physical memory, and takes over the active level 4 page
+/// table.
+/// # Safety
+/// All of physical memory must be mapped at the given offset, and this must only be called once.
+pub unsafe fn init(physical_memory_offset: VirtAddr) -> () {
+    PHYSICAL_MEMORY_OFFSET.call_once(|| physical_memory_offset);
+    MAPPER.call_once(|| IrqMutex::new(OffsetPageTable::new(active_level_4_table(physical_memory_offset), physical_memory_offset)));
+}
+
+/// Gets a mutable reference to the active level 4 page table.
+unsafe fn active_level_4_table(physical_memory_offset: VirtAddr) -> &'static mut PageTable {
+    let (frame, _): (PhysFrame, _) = Cr3::read();
+    let table:      *mut PageTable = (physical_memory_offset + frame.start_address().as_u64()).as_mut_ptr();
+    &mut *table
+}
+
+/// Gets the mapper over the active level 4 page table.
+/// # Panics
+/// Panics if paging has not been initialized.
+fn mapper() -> &'static IrqMutex<OffsetPageTable<'static>> {
+    MAPPER.r#try().expect("Paging has not been initialized")
+}
+
+
+/*
+ * Physical Memory
+ *      Access
+ */
+
+
+/// Gets the virtual address at which all of physical memory is mapped.
+/// # Panics
+/// Panics if paging has not been initialized.
+pub fn physical_memory_offset() -> VirtAddr {
+    *PHYSICAL_MEMORY_OFFSET.r#try().expect("Paging has not been initialized")
+}
+
+/// Gets the virtual address through which a physical address may be accessed.
+pub fn phys_to_virt(address: PhysAddr) -> VirtAddr {
+    physical_memory_offset() + address.as_u64()
+}
+
+
+/*
+ * Page Mapping
+ *      Routines
+ */
+
+
+/// Maps a page to a frame with the given flags, allocating any intermediate page tables from the
+/// global frame allocator.
+/// # Safety
+/// The frame must not already be in use elsewhere in a way that mapping it would violate memory
+/// safety, such as by aliasing memory that is owned by something else.
+pub unsafe fn map(page: Page, frame: PhysFrame, flags: PageTableFlags) -> Result<(), PagingError> {
+    let mut allocator: IrqMutexGuard<BitmapFrameAllocator> = FRAME_ALLOCATOR.lock();
+    mapper().lock().map_to(page, frame, flags, &mut *allocator)?.flush();
+    Ok(())
+}
+
+/// Allocates a fresh frame from the global frame allocator and maps the page to it.
+/// The frame is returned to the allocator if the page could not be mapped.
+pub fn map_new(page: Page, flags: PageTableFlags) -> Result<PhysFrame, PagingError> {
+    let frame: PhysFrame = frame_allocator::allocate_frame().ok_or(PagingError::FrameAllocationFailed)?;
+    match unsafe { map(page, frame, flags) } {
+        Ok(())     => Ok(frame),
+        Err(error) => {
+            unsafe { frame_allocator::deallocate_frame(frame) };
+            Err(error)
+        }
+    }
+}
+
+/// Unmaps a page, returning the frame that it was mapped to.
+/// # Note
+/// The frame is not returned to the frame allocator, as the caller is the one who knows whether it
+/// is still in use.
+pub fn unmap(page: Page) -> Result<PhysFrame, PagingError> {
+    let (frame, flush) = mapper().lock().unmap(page)?;
+    flush.flush();
+    Ok(frame)
+}
+
+/// Replaces the flags of a mapped page.
+/// # Safety
+/// Changing the flags of a page may break any assumptions made about the memory behind it, such as
+/// it remaining writable or executable.
+pub unsafe fn update_flags(page: Page, flags: PageTableFlags) -> Result<(), PagingError> {
+    mapper().lock().update_flags(page, flags)?.flush();
+    Ok(())
+}
+
+/// Translates a virtual address into the physical address it is mapped to, if it is mapped.
+pub fn translate(address: VirtAddr) -> Option<PhysAddr> {
+    mapper().lock().translate_addr(address)
+}
+
+/// Gets the flags that the page containing the given address is mapped with, if it is mapped.
+/// # Note
+/// The flags are those of the final entry, which may belong to a huge page. Use `effective_flags`
+/// to learn what accesses the whole page table hierarchy permits.
+pub fn flags(address: VirtAddr) -> Option<PageTableFlags> {
+    match mapper().lock().translate(address) {
+        TranslateResult::Mapped { flags, .. } => Some(flags),
+        _                                     => None
+    }
+}
+
+/// Gets the flags that the page containing the given address is effectively mapped with, if it is
+/// mapped. An access is only writable or user accessible if every entry walked for it allows so,
+/// and is not executable if any entry forbids it, so these flags are combined across all levels;
+/// every other flag is that of the final entry.
+pub fn effective_flags(address: VirtAddr) -> Option<PageTableFlags> {
+    const INHERITED: PageTableFlags = PageTableFlags::WRITABLE.union(PageTableFlags::USER_ACCESSIBLE);
+
+    let mut mapper: IrqMutexGuard<OffsetPageTable<'static>> = mapper().lock();
+    let indices:    [PageTableIndex; 4]                     = [address.p4_index(), address.p3_index(), address.p2_index(), address.p1_index()];
+    
+    let mut table:     &PageTable     = mapper.level_4_table();
+    let mut allowed:   PageTableFlags = INHERITED;
+    let mut forbidden: PageTableFlags = PageTableFlags::empty();
+    for (level, index) in indices.iter().enumerate() {
+        let flags: PageTableFlags = table[*index].flags();
+        if !flags.contains(PageTableFlags::PRESENT) {
+            return None;
+        }
+
+        allowed   &= flags;
+        forbidden |= flags & PageTableFlags::NO_EXECUTE;
+        if level == indices.len() - 1 || (level > 0 && flags.contains(PageTableFlags::HUGE_PAGE)) {
+            return Some((flags - INHERITED) | allowed | forbidden);
+        }
+        table = unsafe { &*phys_to_virt(table[*index].addr()).as_ptr::<PageTable>() };
+    }
+    unreachable!()
+}
+
+
+/*
+ * Paging
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_translate_identity() -> () {
+    // The VGA buffer is identity mapped by the bootloader.
+    assert_eq!(translate(VirtAddr::new(0xb8000)), Some(PhysAddr::new(0xb8000)));
+}
+
+#[test_case]
+fn test_map_write_read_back() -> () {
+    let page:  Page           = Page::containing_address(VirtAddr::new(0x5555_5555_0000));
+    let flags: PageTableFlags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    let frame: PhysFrame      = map_new(page, flags).expect("Failed to map the page");
+    assert_eq!(translate(page.start_address() + 0x123u64), Some(frame.start_address() + 0x123u64));
+
+    // Write through the new mapping, and read it back through the physical memory offset.
+    let mapped:   *mut u64 = page.start_address().as_mut_ptr();
+    let physical: *mut u64 = phys_to_virt(frame.start_address()).as_mut_ptr();
+    unsafe {
+        mapped.write_volatile(0xf021_f077_f065_f04e);
+        assert_eq!(physical.read_volatile(), 0xf021_f077_f065_f04e);
+    }
+
+    assert_eq!(unsafe { map(page, frame, flags) }, Err(PagingError::AlreadyMapped(frame)));
+    assert_eq!(unmap(page), Ok(frame));
+    assert_eq!(translate(page.start_address()), None);
+    assert_eq!(unmap(page), Err(PagingError::NotMapped));
+    unsafe { frame_allocator::deallocate_frame(frame) };
+}
+
+#[test_case]
+fn test_update_flags() -> () {
+    let page:  Page      = Page::containing_address(VirtAddr::new(0x5555_5556_0000));
+    let frame: PhysFrame = map_new(page, PageTableFlags::PRESENT | PageTableFlags::WRITABLE).unwrap();
+    unsafe { update_flags(page, PageTableFlags::PRESENT).unwrap() };
+    assert_eq!(flags(page.start_address()), Some(PageTableFlags::PRESENT));
+    
+    assert_eq!(unmap(page), Ok(frame));
+    assert_eq!(unsafe { update_flags(page, PageTableFlags::PRESENT) }, Err(PagingError::NotMapped));
+    unsafe { frame_allocator::deallocate_frame(frame) };
+}
+
+#[test_case]
+fn test_effective_flags() -> () {
+    // The kernel's own mappings are never user accessible, whatever their final entry holds.
+    let kernel: PageTableFlags = effective_flags(VirtAddr::new(0xb8000)).unwrap();
+    assert!(kernel.contains(PageTableFlags::PRESENT));
+    assert!(!kernel.contains(PageTableFlags::USER_ACCESSIBLE));
+    
+    let page:  Page           = Page::containing_address(VirtAddr::new(0x5555_5557_0000));
+    let flags: PageTableFlags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    let frame: PhysFrame      = map_new(page, flags).unwrap();
+    assert_eq!(effective_flags(page.start_address()), Some(flags));
+    assert_eq!(effective_flags(page.start_address() + 0x1000u64), None);
+    
+    assert_eq!(unmap(page), Ok(frame));
+    unsafe { frame_allocator::deallocate_frame(frame) };
+}
@@ -0,0 +1,416 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$  /$$           /$$      
+//  /$$__  $$| $$          | $$      
+// | $$  \__/| $$  /$$$$$$ | $$$$$$$ 
+// |  $$$$$$ | $$ |____  $$| $$__  $$
+//  \____  $$| $$  /$$$$$$$| $$  \ $$
+//  /$$  \ $$| $$ /$$__  $$| $$  | $$
+// |  $$$$$$/| $$|  $$$$$$$| $$$$$$$/
+//  \______/ |__/ \_______/|_______/ 
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A slab allocator, which serves fixed-size objects from named caches. Each cache carves whole
+//! frames into slabs of identically sized objects, so allocating and freeing an object never has
+//! to search the heap.
+//!
+
+use core::fmt::{ self, Write };
+use core::mem;
+use core::ptr::NonNull;
+
+use x86_64::structures::paging::PhysFrame;
+
+use crate::drivers::serial::{ SERIAL_1, Uart };
+use crate::sync::irq_mutex::{ IrqMutex, IrqMutexGuard };
+use super::frame_allocator::{ self, FRAME_SIZE };
+use super::paging;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The maximum amount of caches that may be registered for statistics.
+pub const MAX_CACHES: usize = 32;
+
+/// The caches whose statistics may be dumped.
+static CACHES: IrqMutex<[Option<&'static SlabCache>; MAX_CACHES]> = IrqMutex::new([None; MAX_CACHES]);
+
+
+/*
+ * Slab Error &
+ *      Statistics
+ */
+
+
+/// The reasons why a slab operation may fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlabError {
+
+    /// No frame could be allocated for a new slab.
+    OutOfMemory,
+
+    /// Every cache slot is already taken.
+    TooManyCaches,
+
+    /// The cache has already been registered.
+    AlreadyRegistered
+}
+
+/// A snapshot of a cache's usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlabStats {
+    pub name:           &'static str,
+    pub object_size:    usize,
+    pub objects_in_use: usize,
+    pub objects_total:  usize,
+    pub slabs:          usize,
+    pub wasted_bytes:   usize    // The bytes of every slab which cannot hold an object.
+}
+
+impl fmt::Display for SlabStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{:<16} size {:>5} | in use {:>6}/{:<6} | slabs {:>4} | wasted {:>7} bytes",
+            self.name, self.object_size, self.objects_in_use, self.objects_total, self.slabs, self.wasted_bytes
+        )
+    }
+}
+
+
+/*
+ * Slab
+ *      Layout
+ */
+
+
+/// The header at the start of every slab, followed by the slab's objects.
+struct SlabHeader {
+    next:   Option<NonNull<SlabHeader>>,    // The next slab of the same cache.
+    free:   Option<NonNull<FreeObject>>,    // The link of the first free object within this slab.
+    in_use: usize,
+    frame:  PhysFrame
+}
+
+/// The link stored just past the end of every free object, which points to the link of the next
+/// free object of the same slab.
+struct FreeObject {
+    next: Option<NonNull<FreeObject>>
+}
+
+/// The mutable state of a cache.
+struct CacheState {
+    slabs:          Option<NonNull<SlabHeader>>,
+    slab_count:     usize,
+    objects_in_use: usize
+}
+
+// The slabs are only ever touched whilst the cache's lock is held.
+unsafe impl Send for CacheState {}
+
+
+/*
+ * Slab
+ *      Cache
+ */
+
+
+/// A constructor which is run over every object as its slab is created, so objects are always
+/// handed out in a constructed state.
+/// # Note
+/// Objects are not reconstructed when freed, so they should be returned to the cache in the state
+/// the constructor left them in.
+pub type Constructor = fn(NonNull<u8>) -> ();
+
+/// A named cache of fixed-size objects.
+pub struct SlabCache {
+    name:        &'static str,
+    size:        usize,    // The size objects were requested with.
+    link:        usize,    // The offset of an object's free list link from the start of the object.
+    stride:      usize,    // The distance between objects, including their link and padding.
+    offset:      usize,    // The offset of the first object from the start of a slab.
+    per_slab:    usize,
+    constructor: Option<Constructor>,
+    state:       IrqMutex<CacheState>
+}
+
+impl SlabCache {
+
+    /// Creates a new cache for objects of the given size and alignment.
+    /// # Panics
+    /// Panics if the alignment is not a power of two, or if a single object does not fit within a
+    /// slab.
+    pub const fn new(name: &'static str, size: usize, align: usize, constructor: Option<Constructor>) -> Self {
+        assert!(align.is_power_of_two(), "Slab objects must be aligned to a power of two");
+        
+        // The free list link lives past the end of each object, so that it never clobbers the work
+        // of the constructor.
+        let align:  usize = if align > mem::align_of::<FreeObject>() { align } else { mem::align_of::<FreeObject>() };
+        let link:   usize = (size + mem::align_of::<FreeObject>() - 1) & !(mem::align_of::<FreeObject>() - 1);
+        let stride: usize = (link + mem::size_of::<FreeObject>() + align - 1) & !(align - 1);
+        let offset: usize = (mem::size_of::<SlabHeader>() + align - 1) & !(align - 1);
+        assert!(offset + stride <= FRAME_SIZE as usize, "Slab objects must fit within a single frame");
+        
+        SlabCache {
+            name,
+            size,
+            link,
+            stride,
+            offset,
+            per_slab: (FRAME_SIZE as usize - offset) / stride,
+            constructor,
+            state: IrqMutex::new(CacheState {
+                slabs:          None,
+                slab_count:     0,
+                objects_in_use: 0
+            })
+        }
+    }
+
+    /// Creates a new cache for objects of the given type.
+    pub const fn of<T>(name: &'static str, constructor: Option<Constructor>) -> Self {
+        Self::new(name, mem::size_of::<T>(), mem::align_of::<T>(), constructor)
+    }
+
+    /// Gets the name of the cache.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Gets the amount of objects which fit within a single slab.
+    pub fn objects_per_slab(&self) -> usize {
+        self.per_slab
+    }
+
+    /// Allocates an object, growing the cache by another slab if every slab is full.
+    pub fn allocate(&self) -> Result<NonNull<u8>, SlabError> {
+        let mut state: IrqMutexGuard<CacheState>   = self.state.lock();
+        let mut slab:  Option<NonNull<SlabHeader>> = state.slabs;
+        while let Some(mut header) = slab {
+            let header: &mut SlabHeader = unsafe { header.as_mut() };
+            if let Some(link) = header.free {
+                header.free            = unsafe { link.as_ref().next };
+                header.in_use         += 1;
+                state.objects_in_use  += 1;
+                return Ok(self.object_of(link));
+            }
+            slab = header.next;
+        }
+
+        // Every slab is full, so carve out a new one and take its first object.
+        let mut header: NonNull<SlabHeader> = self.create_slab()?;
+        let header_ref: &mut SlabHeader     = unsafe { header.as_mut() };
+        let link:       NonNull<FreeObject> = header_ref.free.expect("A new slab must hold at least one object");
+        header_ref.free    = unsafe { link.as_ref().next };
+        header_ref.in_use  = 1;
+        header_ref.next    = state.slabs;
+        
+        state.slabs           = Some(header);
+        state.slab_count     += 1;
+        state.objects_in_use += 1;
+        Ok(self.object_of(link))
+    }
+
+    /// Returns an object to the cache. If this leaves its slab entirely free and the cache holds
+    /// another slab, the slab's frame is handed back to the frame allocator.
+    /// # Safety
+    /// The object must have been allocated from this cache, and must no longer be in use.
+    pub unsafe fn free(&self, object: NonNull<u8>) -> () {
+        let mut state:  IrqMutexGuard<CacheState> = self.state.lock();
+        let mut header: NonNull<SlabHeader>       = NonNull::new_unchecked((object.as_ptr() as usize & !(FRAME_SIZE as usize - 1)) as *mut SlabHeader);
+        let header_ref: &mut SlabHeader           = header.as_mut();
+        
+        let link: NonNull<FreeObject> = self.link_of(object);
+        link.as_ptr().write(FreeObject { next: header_ref.free });
+        header_ref.free       = Some(link);
+        header_ref.in_use    -= 1;
+        state.objects_in_use -= 1;
+
+        if header_ref.in_use == 0 && state.slab_count > 1 {
+            self.release_slab(&mut state, header);
+        }
+    }
+
+    /// Hands every entirely free slab back to the frame allocator, returning how many were freed.
+    pub fn shrink(&self) -> usize {
+        let mut state: IrqMutexGuard<CacheState> = self.state.lock();
+        let mut freed: usize                     = 0;
+        
+        let mut slab: Option<NonNull<SlabHeader>> = state.slabs;
+        while let Some(header) = slab {
+            slab = unsafe { header.as_ref().next };
+            if unsafe { header.as_ref().in_use } == 0 {
+                unsafe { self.release_slab(&mut state, header) };
+                freed += 1;
+            }
+        }
+        freed
+    }
+
+    /// Takes a snapshot of the cache's usage.
+    pub fn stats(&self) -> SlabStats {
+        let state: IrqMutexGuard<CacheState> = self.state.lock();
+        SlabStats {
+            name:           self.name,
+            object_size:    self.size,
+            objects_in_use: state.objects_in_use,
+            objects_total:  state.slab_count * self.per_slab,
+            slabs:          state.slab_count,
+            wasted_bytes:   state.slab_count * (FRAME_SIZE as usize - self.per_slab * self.size)
+        }
+    }
+
+    /// Allocates a frame for a new slab, threads its objects onto a free list and runs the
+    /// constructor over each of them.
+    fn create_slab(&self) -> Result<NonNull<SlabHeader>, SlabError> {
+        let frame: PhysFrame = frame_allocator::allocate_frame().ok_or(SlabError::OutOfMemory)?;
+        let base:  *mut u8   = paging::phys_to_virt(frame.start_address()).as_mut_ptr();
+        
+        let mut free: Option<NonNull<FreeObject>> = None;
+        for index in (0..self.per_slab).rev() {
+            let object: NonNull<u8> = unsafe { NonNull::new_unchecked(base.add(self.offset + index * self.stride)) };
+            if let Some(constructor) = self.constructor {
+                constructor(object);
+            }
+            
+            let link: NonNull<FreeObject> = self.link_of(object);
+            unsafe { link.as_ptr().write(FreeObject { next: free }) };
+            free = Some(link);
+        }
+
+        let header: *mut SlabHeader = base as *mut SlabHeader;
+        unsafe {
+            header.write(SlabHeader { next: None, free, in_use: 0, frame });
+            Ok(NonNull::new_unchecked(header))
+        }
+    }
+
+    /// Gets the free list link of an object.
+    fn link_of(&self, object: NonNull<u8>) -> NonNull<FreeObject> {
+        unsafe { NonNull::new_unchecked(object.as_ptr().add(self.link) as *mut FreeObject) }
+    }
+
+    /// Gets the object which a free list link belongs to.
+    fn object_of(&self, link: NonNull<FreeObject>) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked((link.as_ptr() as *mut u8).sub(self.link)) }
+    }
+
+    /// Unlinks a slab from the cache and hands its frame back to the frame allocator.
+    /// # Safety
+    /// The slab must belong to this cache and hold no objects in use.
+    unsafe fn release_slab(&self, state: &mut CacheState, slab: NonNull<SlabHeader>) -> () {
+        let next: Option<NonNull<SlabHeader>> = slab.as_ref().next;
+        if state.slabs == Some(slab) {
+            state.slabs = next;
+        } else {
+            let mut current: Option<NonNull<SlabHeader>> = state.slabs;
+            while let Some(mut header) = current {
+                if header.as_ref().next == Some(slab) {
+                    header.as_mut().next = next;
+                    break;
+                }
+                current = header.as_ref().next;
+            }
+        }
+
+        state.slab_count -= 1;
+        frame_allocator::deallocate_frame(slab.as_ref().frame);
+    }
+}
+
+
+/*
+ * Cache Registry
+ *      Routines
+ */
+
+
+/// Registers a cache so that its statistics are included when dumping.
+pub fn register(cache: &'static SlabCache) -> Result<(), SlabError> {
+    let mut caches: IrqMutexGuard<[Option<&'static SlabCache>; MAX_CACHES]> = CACHES.lock();
+    if caches.iter().flatten().any(|registered| core::ptr::eq(*registered, cache)) {
+        return Err(SlabError::AlreadyRegistered);
+    }
+
+    let slot: &mut Option<&'static SlabCache> = caches.iter_mut().find(|slot| slot.is_none()).ok_or(SlabError::TooManyCaches)?;
+    *slot = Some(cache);
+    Ok(())
+}
+
+/// Writes the statistics of every registered cache to the first serial port.
+pub fn dump_stats() -> () {
+    let     caches: [Option<&'static SlabCache>; MAX_CACHES] = *CACHES.lock();
+    let mut serial: IrqMutexGuard<Uart>                       = SERIAL_1.lock();
+    
+    writeln!(serial, "Slab caches:").expect("Printing to serial failed");
+    for cache in caches.iter().flatten() {
+        writeln!(serial, "  {}", cache.stats()).expect("Printing to serial failed");
+    }
+}
+
+
+/*
+ * Slab
+ *      Tests
+ */
+
+
+/// A constructor which fills a test object with a marker.
+#[cfg(test)]
+fn mark_object(object: NonNull<u8>) -> () {
+    unsafe { object.cast::<[u64; 4]>().as_ptr().write([0x51ab; 4]) };
+}
+
+#[cfg(test)]
+static TEST_CACHE: SlabCache = SlabCache::of::<[u64; 4]>("test objects", Some(mark_object));
+
+#[test_case]
+fn test_constructed_objects() -> () {
+    let object: NonNull<u8> = TEST_CACHE.allocate().unwrap();
+    assert_eq!(unsafe { *object.cast::<[u64; 4]>().as_ref() }, [0x51ab; 4]);
+    unsafe { TEST_CACHE.free(object) };
+    TEST_CACHE.shrink();
+}
+
+#[test_case]
+fn test_grow_and_shrink() -> () {
+    const OBJECTS: usize = 512;
+
+    let baseline: usize = frame_allocator::FRAME_ALLOCATOR.lock().free_frames();
+    let mut objects: [Option<NonNull<u8>>; OBJECTS] = [None; OBJECTS];
+    for object in objects.iter_mut() {
+        *object = Some(TEST_CACHE.allocate().unwrap());
+    }
+
+    let stats: SlabStats = TEST_CACHE.stats();
+    assert_eq!(stats.objects_in_use, OBJECTS);
+    assert_eq!(stats.slabs, OBJECTS.div_ceil(TEST_CACHE.objects_per_slab()));
+    assert_eq!(stats.wasted_bytes, stats.slabs * (FRAME_SIZE as usize - TEST_CACHE.objects_per_slab() * 32));
+
+    // Freeing everything must release all but one slab, and shrinking must release the last.
+    for object in objects.iter().flatten() {
+        unsafe { TEST_CACHE.free(*object) };
+    }
+    assert_eq!(TEST_CACHE.stats().objects_in_use, 0);
+    assert_eq!(TEST_CACHE.stats().slabs, 1);
+    assert_eq!(TEST_CACHE.shrink(), 1);
+    assert_eq!(frame_allocator::FRAME_ALLOCATOR.lock().free_frames(), baseline);
+}
+
+#[test_case]
+fn test_register_and_dump() -> () {
+    static DUMPED_CACHE: SlabCache = SlabCache::new("dumped objects", 100, 4, None);
+    
+    assert_eq!(register(&DUMPED_CACHE), Ok(()));
+    assert_eq!(register(&DUMPED_CACHE), Err(SlabError::AlreadyRegistered));
+    dump_stats();
+}
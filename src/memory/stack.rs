@@ -0,0 +1,250 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$   /$$                         /$$      
+//  /$$__  $$ | $$                        | $$      
+// | $$  \__//$$$$$$    /$$$$$$   /$$$$$$$| $$   /$$
+// |  $$$$$$|_  $$_/   |____  $$ /$$_____/| $$  /$$/
+//  \____  $$ | $$      /$$$$$$$| $$      | $$$$$$/ 
+//  /$$  \ $$ | $$ /$$ /$$__  $$| $$      | $$_  $$ 
+// |  $$$$$$/ |  $$$$/|  $$$$$$$|  $$$$$$$| $$ \  $$
+//  \______/   \___/   \_______/ \_______/|__/  \__/
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! Allocates kernel stacks within a dedicated region of virtual memory, leaving an unmapped guard
+//! page below each stack so that an overflow faults rather than silently corrupting whatever lies
+//! beneath it.
+//!
+
+use x86_64::VirtAddr;
+use x86_64::structures::paging::{ Page, PageSize, PageTableFlags, PhysFrame, Size4KiB };
+
+use crate::sync::irq_mutex::IrqMutex;
+use super::frame_allocator;
+use super::paging::{ self, PagingError };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The virtual address at which the stack region begins.
+pub const STACK_REGION_START: u64 = 0x6666_0000_0000;
+
+/// The size of the stack region, in bytes.
+pub const STACK_REGION_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// The size of a single page, in bytes.
+const PAGE_SIZE: u64 = Size4KiB::SIZE;
+
+/// The global stack allocator.
+static STACK_ALLOCATOR: IrqMutex<StackAllocator> = IrqMutex::new(StackAllocator::new(STACK_REGION_START, STACK_REGION_SIZE));
+
+
+/*
+ * Stack
+ *      Error
+ */
+
+
+/// The reasons why a stack may fail to be allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackError {
+
+    /// The stack region has no room left for a stack of the requested size.
+    RegionExhausted,
+
+    /// The stack's pages could not be mapped.
+    Paging(PagingError)
+}
+
+impl From<PagingError> for StackError {
+    fn from(error: PagingError) -> Self {
+        Self::Paging(error)
+    }
+}
+
+
+/*
+ * Kernel
+ *      Stack
+ */
+
+
+/// A mapped kernel stack, with an unmapped guard page directly below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KernelStack {
+    guard:  Page,
+    bottom: VirtAddr,
+    top:    VirtAddr
+}
+
+impl KernelStack {
+
+    /// Gets the address just past the highest byte of the stack, which is where the stack pointer
+    /// starts as stacks grow downward on x86-64.
+    pub fn top(&self) -> VirtAddr {
+        self.top
+    }
+
+    /// Gets the address of the lowest byte of the stack.
+    pub fn bottom(&self) -> VirtAddr {
+        self.bottom
+    }
+
+    /// Gets the size of the stack, in bytes.
+    pub fn size(&self) -> u64 {
+        self.top - self.bottom
+    }
+
+    /// Gets the unmapped guard page below the stack.
+    pub fn guard_page(&self) -> Page {
+        self.guard
+    }
+
+    /// Whether the given address lies within the stack.
+    pub fn contains(&self, address: VirtAddr) -> bool {
+        (self.bottom..self.top).contains(&address)
+    }
+
+    /// Whether the given address lies within the guard page, which means that a fault at that
+    /// address was caused by the stack overflowing.
+    pub fn is_guard_hit(&self, address: VirtAddr) -> bool {
+        Page::containing_address(address) == self.guard
+    }
+
+    /// Iterates over the stack's mapped pages.
+    fn pages(&self) -> impl Iterator<Item = Page> {
+        Page::range(Page::containing_address(self.bottom), Page::containing_address(self.top))
+    }
+}
+
+
+/*
+ * Stack
+ *      Allocator
+ */
+
+
+/// Hands out stacks from a region of virtual memory, one after the other.
+/// # Note
+/// The virtual memory of a freed stack is never reused, as the region is far larger than the
+/// kernel will ever need.
+pub struct StackAllocator {
+    next: u64,
+    end:  u64
+}
+
+impl StackAllocator {
+
+    /// Creates a new allocator over the given region of virtual memory.
+    pub const fn new(start: u64, size: u64) -> Self {
+        StackAllocator {
+            next: start,
+            end:  start + size
+        }
+    }
+
+    /// Allocates a stack of the given amount of pages, mapping each to a fresh frame and leaving
+    /// the page below it unmapped.
+    pub fn allocate(&mut self, pages: u64) -> Result<KernelStack, StackError> {
+        let size: u64 = (pages + 1) * PAGE_SIZE;
+        if pages == 0 || self.end - self.next < size {
+            return Err(StackError::RegionExhausted);
+        }
+
+        let guard: Page        = Page::containing_address(VirtAddr::new(self.next));
+        let stack: KernelStack = KernelStack {
+            guard,
+            bottom: guard.start_address() + PAGE_SIZE,
+            top:    guard.start_address() + size
+        };
+        self.next += size;
+
+        let flags: PageTableFlags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+        for page in stack.pages() {
+            if let Err(error) = paging::map_new(page, flags) {
+                unsafe { unmap_pages(Page::range(Page::containing_address(stack.bottom), page)) };
+                return Err(error.into());
+            }
+        }
+        Ok(stack)
+    }
+}
+
+
+/*
+ * Global Allocator
+ *      Routines
+ */
+
+
+/// Allocates a stack of the given amount of pages from the global stack allocator.
+pub fn allocate(pages: u64) -> Result<KernelStack, StackError> {
+    STACK_ALLOCATOR.lock().allocate(pages)
+}
+
+/// Unmaps a stack and returns its frames to the frame allocator.
+/// # Safety
+/// The stack must no longer be in use, neither by any thread of execution nor by the TSS.
+pub unsafe fn free(stack: KernelStack) -> () {
+    unmap_pages(stack.pages());
+}
+
+/// Unmaps the given pages, returning their frames to the frame allocator.
+unsafe fn unmap_pages(pages: impl Iterator<Item = Page>) -> () {
+    for page in pages {
+        let frame: PhysFrame = paging::unmap(page).expect("A stack page was not mapped");
+        frame_allocator::deallocate_frame(frame);
+    }
+}
+
+
+/*
+ * Stack
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_stack_mapped_with_guard() -> () {
+    let stack: KernelStack = allocate(4).unwrap();
+    assert_eq!(stack.size(), 4 * PAGE_SIZE);
+    assert_eq!(stack.guard_page().start_address() + PAGE_SIZE, stack.bottom());
+    assert!(paging::translate(stack.bottom()).is_some());
+    assert!(paging::translate(stack.top() - 1u64).is_some());
+    assert!(paging::translate(stack.guard_page().start_address()).is_none());
+
+    // The stack must be writable from top to bottom.
+    let top:    *mut u64 = (stack.top() - 8u64).as_mut_ptr();
+    let bottom: *mut u64 = stack.bottom().as_mut_ptr();
+    unsafe {
+        top.write_volatile(1);
+        bottom.write_volatile(2);
+        assert_eq!(top.read_volatile() + bottom.read_volatile(), 3);
+    }
+
+    assert!(stack.is_guard_hit(stack.bottom() - 1u64));
+    assert!(!stack.is_guard_hit(stack.bottom()));
+    unsafe { free(stack) };
+    assert!(paging::translate(stack.bottom()).is_none());
+}
+
+#[test_case]
+fn test_stacks_do_not_overlap() -> () {
+    let first:  KernelStack = allocate(2).unwrap();
+    let second: KernelStack = allocate(2).unwrap();
+    assert!(!first.contains(second.bottom()) && !second.contains(first.bottom()));
+    assert!(first.guard_page() != second.guard_page());
+    unsafe {
+        free(first);
+        free(second);
+    }
+}
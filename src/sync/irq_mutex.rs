@@ -0,0 +1,197 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$ /$$$$$$$   /$$$$$$        /$$      /$$             /$$                        
+// |_  $$_/| $$__  $$ /$$__  $$      | $$$    /$$$            | $$                        
+//   | $$  | $$  \ $$| $$  \ $$      | $$$$  /$$$$ /$$   /$$ /$$$$$$    /$$$$$$  /$$   /$$
+//   | $$  | $$$$$$$/| $$  | $$      | $$ $$/$$ $$| $$  | $$|_  $$_/   /$$__  $$|  $$ /$$/
+//   | $$  | $$__  $$| $$  | $$      | $$  $$$| $$| $$  | $$  | $$    | $$$$$$$$ \  $$$$/ 
+//   | $$  | $$  \ $$| $$/$$ $$      | $$\  $ | $$| $$  | $$  | $$ /$$| $$_____/  >$$  $$ 
+//  /$$$$$$| $$  | $$|  $$$$$$/      | $$ \/  | $$|  $$$$$$/  |  $$$$/|  $$$$$$$ /$$/\  $$
+// |______/|__/  |__/ \____ $$$      |__/     |__/ \______/    \___/   \_______/|__/  \__/
+//                         \__/                                                           
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A spinlock which disables interrupts for as long as it is held, so that it may be shared
+//! between regular kernel code and interrupt handlers without deadlocking.
+//!
+
+use core::ops::{ Deref, DerefMut };
+use core::mem::ManuallyDrop;
+
+use x86_64::instructions::interrupts;
+use spin::{ Mutex, MutexGuard };
+
+
+/*
+ * IRQ-Safe
+ *      Mutex
+ */
+
+
+/// A spinlock which disables interrupts whilst held.
+/// # Note
+/// A regular spinlock taken with interrupts enabled deadlocks as soon as an interrupt handler
+/// attempts to take it whilst the interrupted code holds it, as the handler spins forever on a lock
+/// that can only be released once the handler returns. Disabling interrupts for the lifetime of the
+/// guard makes this impossible on a single core.
+pub struct IrqMutex<T> {
+    inner: Mutex<T>
+}
+
+impl <T> IrqMutex<T> {
+
+    /// Creates a new lock around the given data.
+    pub const fn new(data: T) -> Self {
+        IrqMutex {
+            inner: Mutex::new(data)
+        }
+    }
+
+    /// Disables interrupts and takes the lock, spinning until it is available.
+    /// # Note
+    /// Interrupts are only re-enabled once the guard is dropped if they were enabled beforehand, so
+    /// that nested locks and locks taken within interrupt handlers behave correctly.
+    pub fn lock(&self) -> IrqMutexGuard<'_, T> {
+        let were_enabled: bool = interrupts::are_enabled();
+        interrupts::disable();
+
+        IrqMutexGuard {
+            guard: ManuallyDrop::new(self.inner.lock()),
+            were_enabled
+        }
+    }
+
+    /// Disables interrupts and attempts to take the lock, restoring the interrupt state and
+    /// returning `None` if it is already held.
+    pub fn try_lock(&self) -> Option<IrqMutexGuard<'_, T>> {
+        let were_enabled: bool = interrupts::are_enabled();
+        interrupts::disable();
+
+        match self.inner.try_lock() {
+            Some(guard) => Some(IrqMutexGuard {
+                guard: ManuallyDrop::new(guard),
+                were_enabled
+            }),
+            None => {
+                if were_enabled {
+                    interrupts::enable();
+                }
+                None
+            }
+        }
+    }
+
+    /// Forcibly releases the lock, regardless of whether it is held.
+    /// # Safety
+    /// This is only sound if the holder of the lock can never touch the data again, such as when
+    /// printing from a panic that interrupted the holder.
+    pub unsafe fn force_unlock(&self) -> () {
+        self.inner.force_unlock();
+    }
+
+    /// Takes the lock, forcibly releasing it first if it is already held. This is for contexts that
+    /// may have interrupted the holder, such as exception handlers and panics, where waiting for the
+    /// lock would never end.
+    /// # Safety
+    /// The holder of the lock must not touch the data until the returned guard is dropped, such as
+    /// because the caller interrupted it, and the data must remain usable even if the holder was
+    /// interrupted midway through changing it.
+    pub unsafe fn force_lock(&self) -> IrqMutexGuard<'_, T> {
+        match self.try_lock() {
+            Some(guard) => guard,
+            None        => {
+                self.force_unlock();
+                self.lock()
+            }
+        }
+    }
+}
+
+/// A guard which releases its lock and restores the previous interrupt state once dropped.
+pub struct IrqMutexGuard<'a, T> {
+    guard:        ManuallyDrop<MutexGuard<'a, T>>,
+    were_enabled: bool
+}
+
+impl <T> Deref for IrqMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl <T> DerefMut for IrqMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl <T> Drop for IrqMutexGuard<'_, T> {
+
+    /// Releases the lock before restoring interrupts, so that no interrupt can arrive whilst the
+    /// lock is still held.
+    fn drop(&mut self) -> () {
+        unsafe {
+            ManuallyDrop::drop(&mut self.guard);
+        }
+        if self.were_enabled {
+            interrupts::enable();
+        }
+    }
+}
+
+
+/*
+ * IRQ Mutex
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_lock_disables_interrupts() -> () {
+    let mutex: IrqMutex<u32> = IrqMutex::new(0);
+    assert!(interrupts::are_enabled());
+
+    {
+        let mut guard: IrqMutexGuard<u32> = mutex.lock();
+        *guard += 1;
+        assert!(!interrupts::are_enabled());
+        assert!(mutex.try_lock().is_none());
+        assert!(!interrupts::are_enabled());
+    }
+    assert!(interrupts::are_enabled());
+    assert_eq!(*mutex.lock(), 1);
+}
+
+#[test_case]
+fn test_nested_locks_restore_state() -> () {
+    let outer: IrqMutex<()> = IrqMutex::new(());
+    let inner: IrqMutex<()> = IrqMutex::new(());
+
+    let outer_guard: IrqMutexGuard<()> = outer.lock();
+    drop(inner.lock());
+    assert!(!interrupts::are_enabled(), "Dropping a nested guard re-enabled interrupts");
+    drop(outer_guard);
+    assert!(interrupts::are_enabled());
+}
+
+#[test_case]
+fn test_force_lock_takes_held_lock() -> () {
+    let mutex: IrqMutex<u32> = IrqMutex::new(0);
+
+    let held: IrqMutexGuard<u32> = mutex.lock();
+    {
+        let mut forced: IrqMutexGuard<u32> = unsafe { mutex.force_lock() };
+        *forced = 7;
+    }
+    core::mem::forget(held);    // The holder never touches the data again, as with a panic.
+    interrupts::enable();
+    assert_eq!(*mutex.lock(), 7);
+}
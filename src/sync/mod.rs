@@ -0,0 +1,3 @@
+
+pub mod ring_buffer;
+pub mod irq_mutex;
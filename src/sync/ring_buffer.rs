@@ -0,0 +1,150 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$  /$$                           /$$$$$$$             /$$$$$$   /$$$$$$                   
+// | $$__  $$|__/                          | $$__  $$           /$$__  $$ /$$__  $$                  
+// | $$  \ $$ /$$ /$$$$$$$   /$$$$$$       | $$  \ $$ /$$   /$$| $$  \__/| $$  \__//$$$$$$   /$$$$$$ 
+// | $$$$$$$/| $$| $$__  $$ /$$__  $$      | $$$$$$$ | $$  | $$| $$$$    | $$$$   /$$__  $$ /$$__  $$
+// | $$__  $$| $$| $$  \ $$| $$  \ $$      | $$__  $$| $$  | $$| $$_/    | $$_/  | $$$$$$$$| $$  \__/
+// | $$  \ $$| $$| $$  | $$| $$  | $$      | $$  \ $$| $$  | $$| $$      | $$    | $$_____/| $$      
+// | $$  | $$| $$| $$  | $$|  $$$$$$$      | $$$$$$$/|  $$$$$$/| $$      | $$    |  $$$$$$$| $$      
+// |__/  |__/|__/|__/  |__/ \____  $$      |_______/  \______/ |__/      |__/     \_______/|__/      
+//                          /$$  \ $$                                                                
+//                         |  $$$$$$/                                                                
+//                          \______/                                                                 
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! A fixed-capacity, lock-free ring buffer that allows a single producer (such as an interrupt
+//! handler) to hand values off to a single consumer without either side ever blocking.
+//!
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{ AtomicUsize, Ordering };
+
+
+/*
+ * Ring
+ *      Buffer
+ */
+
+
+/// A lock-free single-producer, single-consumer ring buffer holding up to `N` values.
+/// # Note
+/// Pushing and popping are each safe to call from an interrupt handler, but only one context may
+/// push and only one context may pop at any given time. The head and tail are free-running
+/// counters, so the buffer holds exactly `N` values rather than `N - 1`.
+pub struct RingBuffer<T: Copy, const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<T>>; N],
+    head:  AtomicUsize,    // The total amount of values that have been popped.
+    tail:  AtomicUsize     // The total amount of values that have been pushed.
+}
+
+unsafe impl <T: Copy + Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+impl <T: Copy, const N: usize> RingBuffer<T, N> {
+
+    /// Creates a new, empty ring buffer.
+    pub const fn new() -> Self {
+        RingBuffer {
+            slots: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head:  AtomicUsize::new(0),
+            tail:  AtomicUsize::new(0)
+        }
+    }
+
+    /// Pushes a value onto the back of the buffer, handing the value back if the buffer is full.
+    pub fn push(&self, value: T) -> Result<(), T> {
+        let tail: usize = self.tail.load(Ordering::Relaxed);
+        let head: usize = self.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) >= N {
+            return Err(value);
+        }
+
+        unsafe {
+            (*self.slots[tail % N].get()).write(value);
+        }
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Pops the value at the front of the buffer, if there is one.
+    pub fn pop(&self) -> Option<T> {
+        let head: usize = self.head.load(Ordering::Relaxed);
+        let tail: usize = self.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+
+        let value: T = unsafe { (*self.slots[head % N].get()).assume_init() };
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Gets the amount of values currently held in the buffer.
+    pub fn len(&self) -> usize {
+        self.tail.load(Ordering::Acquire).wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Whether the buffer currently holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the buffer is currently full, in which case any further pushes are rejected.
+    pub fn is_full(&self) -> bool {
+        self.len() >= N
+    }
+
+    /// Gets the maximum amount of values the buffer can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl <T: Copy, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/*
+ * Ring Buffer
+ *      Tests
+ */
+
+
+#[test_case]
+fn test_ring_buffer_fifo() -> () {
+    let buffer: RingBuffer<u8, 4> = RingBuffer::new();
+    assert!(buffer.is_empty());
+
+    for value in 0..4 {
+        buffer.push(value).unwrap();
+    }
+    assert!(buffer.is_full());
+    assert_eq!(buffer.push(4), Err(4));
+
+    for value in 0..4 {
+        assert_eq!(buffer.pop(), Some(value));
+    }
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test_case]
+fn test_ring_buffer_wrap_around() -> () {
+    let buffer: RingBuffer<usize, 3> = RingBuffer::new();
+    for value in 0..100 {
+        buffer.push(value).unwrap();
+        buffer.push(value + 1).unwrap();
+        assert_eq!(buffer.pop(), Some(value));
+        assert_eq!(buffer.pop(), Some(value + 1));
+        assert_eq!(buffer.len(), 0);
+    }
+}
@@ -0,0 +1,277 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$$                                           /$$     /$$                              
+// | $$_____/                                          | $$    |__/                              
+// | $$       /$$   /$$  /$$$$$$$  /$$$$$$   /$$$$$$  /$$$$$$   /$$  /$$$$$$  /$$$$$$$   /$$$$$$$
+// | $$$$$   |  $$ /$$/ /$$_____/ /$$__  $$ /$$__  $$|_  $$_/  | $$ /$$__  $$| $$__  $$ /$$_____/
+// | $$__/    \  $$$$/ | $$      | $$$$$$$$| $$  \ $$  | $$    | $$| $$  \ $$| $$  \ $$|  $$$$$$ 
+// | $$        >$$  $$ | $$      | $$_____/| $$  | $$  | $$ /$$| $$| $$  | $$| $$  | $$ \____  $$
+// | $$$$$$$$ /$$/\  $$|  $$$$$$$|  $$$$$$$| $$$$$$$/  |  $$$$/| $$|  $$$$$$/| $$  | $$ /$$$$$$$/
+// |________/|__/  \__/ \_______/ \_______/| $$____/    \___/  |__/ \______/ |__/  |__/|_______/ 
+//                                         | $$                                                  
+//                                         | $$                                                  
+//                                         |__/                                                  
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds a table of exceptions that are each raised in turn, and tests that the OS's exception
+//! handler routes every one through the exception policy with a complete report. The policy then
+//! removes each exception's cause, so that the restarted instruction succeeds and the next case may
+//! run.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use core::arch::asm;
+use core::panic::PanicInfo;
+use core::sync::atomic::{ AtomicBool, AtomicU16, AtomicU32, AtomicUsize, Ordering };
+
+use alloc::{ format, string::String };
+use bootloader::{ BootInfo, entry_point };
+use x86_64::registers::control::{ Cr0, Cr0Flags, Cr4, Cr4Flags };
+use x86_64::structures::idt::{ DescriptorTable, SelectorErrorCode };
+
+use solas_os::instructions::exceptions::{ Exception, ExceptionAction, ExceptionReport };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// Describes a single exception to raise, along with what its report is expected to contain.
+struct ExceptionCase {
+
+    /// The exception the case raises.
+    exception: Exception,
+
+    /// The error code the exception is expected to push.
+    error_code: Option<u64>,
+
+    /// The first line of the formatted report.
+    header: &'static str,
+
+    /// Raises the exception.
+    raise: fn() -> (),
+
+    /// Checks anything particular to the exception's report, and then removes its cause.
+    recover: fn(&ExceptionReport) -> ()
+}
+
+/// Every exception raised by the test, in order.
+const CASES: [ExceptionCase; 5] = [
+    ExceptionCase {
+        exception:  Exception::DivideError,
+        error_code: None,
+        header:     "EXCEPTION: DIVIDE ERROR (#DE, vector 0)",
+        raise:      raise_divide_error,
+        recover:    recover_divide_error
+    },
+    ExceptionCase {
+        exception:  Exception::InvalidOpcode,
+        error_code: None,
+        header:     "EXCEPTION: INVALID OPCODE (#UD, vector 6)",
+        raise:      raise_invalid_opcode,
+        recover:    recover_invalid_opcode
+    },
+    ExceptionCase {
+        exception:  Exception::DeviceNotAvailable,
+        error_code: None,
+        header:     "EXCEPTION: DEVICE NOT AVAILABLE (#NM, vector 7)",
+        raise:      raise_device_not_available,
+        recover:    recover_device_not_available
+    },
+    ExceptionCase {
+        exception:  Exception::GeneralProtectionFault,
+        error_code: Some(INVALID_SELECTOR as u64 & !0b11),
+        header:     "EXCEPTION: GENERAL PROTECTION FAULT (#GP, vector 13)",
+        raise:      raise_general_protection_fault,
+        recover:    recover_general_protection_fault
+    },
+    ExceptionCase {
+        exception:  Exception::SimdFloatingPoint,
+        error_code: None,
+        header:     "EXCEPTION: SIMD FLOATING-POINT EXCEPTION (#XM, vector 19)",
+        raise:      raise_simd_floating_point,
+        recover:    recover_simd_floating_point
+    }
+];
+
+/// A segment selector whose index lies far outside of the GDT.
+const INVALID_SELECTOR: u16 = 0x1238;
+
+/// The MXCSR value with every SIMD floating-point exception masked, and the same with the divide by
+/// zero exception unmasked.
+const MXCSR_MASKED:               u32 = 0x1f80;
+const MXCSR_UNMASKED_ZERO_DIVIDE: u32 = MXCSR_MASKED & !(1 << 9);
+
+/// The index of the case currently being raised.
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the current case's exception has been raised and recovered from.
+static RECOVERED: AtomicBool = AtomicBool::new(false);
+
+/// The divisor of the faulting division, which is zero until the divide error is recovered from.
+static DIVISOR: AtomicU32 = AtomicU32::new(0);
+
+/// The selector loaded into DS, which is invalid until the general protection fault is recovered
+/// from.
+static SELECTOR: AtomicU16 = AtomicU16::new(INVALID_SELECTOR);
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    let tested: [Exception; CASES.len()] = CASES.map(|case| case.exception);
+    solas_os::init_exception_test(boot_info, &tested, test_exception_policy);
+    solas_os::run_exception_test("exceptions::exceptions", test_exceptions);
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+fn test_exceptions() -> () {
+    for (index, case) in CASES.iter().enumerate() {
+        CURRENT.store(index, Ordering::SeqCst);
+        RECOVERED.store(false, Ordering::SeqCst);
+        (case.raise)();
+        assert!(RECOVERED.load(Ordering::SeqCst), "Execution continued without {:?} being raised!", case.exception);
+    }
+}
+
+fn raise_divide_error() -> () {
+    unsafe {
+        asm!("div dword ptr [rip + {divisor}]", divisor = sym DIVISOR, inout("eax") 1 => _, inout("edx") 0 => _);
+    }
+}
+
+fn recover_divide_error(_: &ExceptionReport) -> () {
+    DIVISOR.store(1, Ordering::SeqCst);
+}
+
+/// Raises an invalid opcode with an SSE instruction whilst SSE is disabled.
+/// # Note
+/// The kernel is built without SSE, so no XMM register clobbered here holds anything of its own.
+fn raise_invalid_opcode() -> () {
+    unsafe {
+        Cr4::update(|flags| flags.remove(Cr4Flags::OSFXSR));
+        asm!("xorps xmm0, xmm0");
+    }
+}
+
+fn recover_invalid_opcode(report: &ExceptionReport) -> () {
+
+    // The faulting instruction pointer must point at the `xorps` itself.
+    let instruction: [u8; 3] = unsafe { *report.stack_frame.instruction_pointer.as_ptr::<[u8; 3]>() };
+    assert_eq!(instruction, [0x0f, 0x57, 0xc0]);
+    unsafe { Cr4::update(|flags| flags.insert(Cr4Flags::OSFXSR)) };
+}
+
+fn raise_device_not_available() -> () {
+    unsafe {
+        Cr0::update(|flags| flags.insert(Cr0Flags::TASK_SWITCHED));
+        asm!("fninit");
+    }
+    assert!(!Cr0::read().contains(Cr0Flags::TASK_SWITCHED));
+}
+
+fn recover_device_not_available(_: &ExceptionReport) -> () {
+    unsafe { Cr0::update(|flags| flags.remove(Cr0Flags::TASK_SWITCHED)) };
+}
+
+fn raise_general_protection_fault() -> () {
+    unsafe {
+        asm!("mov ds, word ptr [rip + {selector}]", selector = sym SELECTOR);
+    }
+}
+
+/// Checks the decoded selector error code, and then swaps the selector for the null selector, which
+/// may always be loaded into DS in long mode.
+fn recover_general_protection_fault(report: &ExceptionReport) -> () {
+    let selector: SelectorErrorCode = report.selector_error_code().expect("No selector error code was reported");
+    assert_eq!(selector.descriptor_table(), DescriptorTable::Gdt);
+    assert_eq!(selector.index(), (INVALID_SELECTOR >> 3) as u64);
+
+    let output: String = format!("{}", report);
+    assert!(output.contains("Error Code:    0x1238"));
+    assert!(output.contains("Selector:      Gdt index 0x247"));
+    SELECTOR.store(0, Ordering::SeqCst);
+}
+
+/// Raises a SIMD floating-point exception by dividing by zero with the exception unmasked.
+/// # Note
+/// The kernel is built without SSE, so no XMM register clobbered here holds anything of its own.
+fn raise_simd_floating_point() -> () {
+    unsafe {
+        Cr0::update(|flags| flags.remove(Cr0Flags::EMULATE_COPROCESSOR));
+        Cr4::update(|flags| flags.insert(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE));
+        asm!(
+            "ldmxcsr [{mxcsr}]",
+            "xorps xmm0, xmm0",
+            "mov {one:e}, 0x3f800000",
+            "movd xmm1, {one:e}",
+            "divss xmm1, xmm0",
+            mxcsr = in(reg) &MXCSR_UNMASKED_ZERO_DIVIDE,
+            one   = out(reg) _
+        );
+    }
+}
+
+fn recover_simd_floating_point(_: &ExceptionReport) -> () {
+    unsafe { asm!("ldmxcsr [{mxcsr}]", mxcsr = in(reg) &MXCSR_MASKED) };
+}
+
+
+/*
+ * Test Exception
+ *      Policy
+ */
+
+
+/// Checks the report of the current case against its table entry, and then recovers from the
+/// exception so that the restarted instruction succeeds.
+fn test_exception_policy(report: &ExceptionReport) -> ExceptionAction {
+    let case: &ExceptionCase = &CASES[CURRENT.load(Ordering::SeqCst)];
+    assert_eq!(report.exception,  case.exception);
+    assert_eq!(report.error_code, case.error_code);
+    assert!(!report.from_user_mode());
+    assert!(!RECOVERED.swap(true, Ordering::SeqCst), "{:?} was raised again after recovering!", case.exception);
+
+    let output: String = format!("{}", report);
+    assert!(output.starts_with(case.header));
+    assert_eq!(output.contains("Error Code:"), case.error_code.is_some());
+    assert!(output.contains("instruction_pointer"));
+
+    (case.recover)(report);
+    ExceptionAction::Resume
+}
@@ -0,0 +1,127 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                                      /$$$$$$  /$$ /$$                                 /$$     /$$                    
+// | $$  | $$                                     /$$__  $$| $$| $$                                | $$    |__/                    
+// | $$  | $$  /$$$$$$   /$$$$$$   /$$$$$$       | $$  \ $$| $$| $$  /$$$$$$   /$$$$$$$  /$$$$$$  /$$$$$$   /$$  /$$$$$$  /$$$$$$$ 
+// | $$$$$$$$ /$$__  $$ |____  $$ /$$__  $$      | $$$$$$$$| $$| $$ /$$__  $$ /$$_____/ |____  $$|_  $$_/  | $$ /$$__  $$| $$__  $$
+// | $$__  $$| $$$$$$$$  /$$$$$$$| $$  \ $$      | $$__  $$| $$| $$| $$  \ $$| $$        /$$$$$$$  | $$    | $$| $$  \ $$| $$  \ $$
+// | $$  | $$| $$_____/ /$$__  $$| $$  | $$      | $$  | $$| $$| $$| $$  | $$| $$       /$$__  $$  | $$ /$$| $$| $$  | $$| $$  | $$
+// | $$  | $$|  $$$$$$$|  $$$$$$$| $$$$$$$/      | $$  | $$| $$| $$|  $$$$$$/|  $$$$$$$|  $$$$$$$  |  $$$$/| $$|  $$$$$$/| $$  | $$
+// |__/  |__/ \_______/ \_______/| $$____/       |__/  |__/|__/|__/ \______/  \_______/ \_______/   \___/  |__/ \______/ |__/  |__/
+//                               | $$                                                                                              
+//                               | $$                                                                                              
+//                               |__/                                                                                              
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that allocate from the kernel heap, and tests that freed memory is reused.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use core::panic::PanicInfo;
+
+use alloc::{ boxed::Box, vec::Vec, string::String };
+use bootloader::{ BootInfo, entry_point };
+
+use solas_os::memory::heap::{ self, HEAP_SIZE };
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+#[test_case]
+fn test_simple_allocation() -> () {
+    let value_1: Box<u64> = Box::new(41);
+    let value_2: Box<u64> = Box::new(13);
+    assert_eq!(*value_1, 41);
+    assert_eq!(*value_2, 13);
+}
+
+#[test_case]
+fn test_large_vec() -> () {
+    let n:       u64      = 1000;
+    let mut vec: Vec<u64> = Vec::new();
+    for i in 0..n {
+        vec.push(i);
+    }
+    assert_eq!(vec.iter().sum::<u64>(), (n - 1) * n / 2);
+}
+
+#[test_case]
+fn test_string() -> () {
+    let mut string: String = String::from("Solas");
+    string.push_str(" OS");
+    assert_eq!(string, "Solas OS");
+}
+
+#[test_case]
+fn test_many_boxes() -> () {
+    // Allocates far more than the heap holds in total, which only succeeds if freed memory is
+    // reused.
+    for i in 0..HEAP_SIZE {
+        let x: Box<usize> = Box::new(i);
+        assert_eq!(*x, i);
+    }
+}
+
+#[test_case]
+fn test_many_boxes_long_lived() -> () {
+    let long_lived: Box<usize> = Box::new(1);
+    for i in 0..HEAP_SIZE {
+        let x: Box<usize> = Box::new(i);
+        assert_eq!(*x, i);
+    }
+    assert_eq!(*long_lived, 1);
+}
+
+#[test_case]
+fn test_reuse_after_free() -> () {
+    let baseline: usize = heap::used();
+    let first:    *const [u8; 256] = &*Box::new([0u8; 256]);
+    assert_eq!(heap::used(), baseline);
+
+    let second: Box<[u8; 256]> = Box::new([1u8; 256]);
+    assert_eq!(&*second as *const [u8; 256], first);
+    assert!(heap::used() > baseline);
+    
+    drop(second);
+    assert_eq!(heap::used(), baseline);
+}
@@ -0,0 +1,147 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                                     /$$$$$$$                                /$$                                         /$$      
+// | $$  | $$                                    | $$__  $$                              | $$                                        | $$      
+// | $$  | $$  /$$$$$$   /$$$$$$   /$$$$$$       | $$  \ $$  /$$$$$$  /$$$$$$$   /$$$$$$$| $$$$$$$  /$$$$$$/$$$$   /$$$$$$   /$$$$$$ | $$   /$$
+// | $$$$$$$$ /$$__  $$ |____  $$ /$$__  $$      | $$$$$$$  /$$__  $$| $$__  $$ /$$_____/| $$__  $$| $$_  $$_  $$ |____  $$ /$$__  $$| $$  /$$/
+// | $$__  $$| $$$$$$$$  /$$$$$$$| $$  \ $$      | $$__  $$| $$$$$$$$| $$  \ $$| $$      | $$  \ $$| $$ \ $$ \ $$  /$$$$$$$| $$  \__/| $$$$$$/ 
+// | $$  | $$| $$_____/ /$$__  $$| $$  | $$      | $$  \ $$| $$_____/| $$  | $$| $$      | $$  | $$| $$ | $$ | $$ /$$__  $$| $$      | $$_  $$ 
+// | $$  | $$|  $$$$$$$|  $$$$$$$| $$$$$$$/      | $$$$$$$/|  $$$$$$$| $$  | $$|  $$$$$$$| $$  | $$| $$ | $$ | $$|  $$$$$$$| $$      | $$ \  $$
+// |__/  |__/ \_______/ \_______/| $$____/       |_______/  \_______/|__/  |__/ \_______/|__/  |__/|__/ |__/ |__/ \_______/|__/      |__/  \__/
+//                               | $$                                                                                                          
+//                               | $$                                                                                                          
+//                               |__/                                                                                                          
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds benchmarks that allocate and free from the kernel heap in common patterns, and report
+//! how many cycles each pattern took over the serial interface so heap strategies may be compared.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use core::panic::PanicInfo;
+use core::arch::x86_64::_rdtsc;
+
+use alloc::{ boxed::Box, vec::Vec };
+use bootloader::{ BootInfo, entry_point };
+
+use solas_os::serial_println;
+use solas_os::memory::heap;
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The amount of times each pattern is repeated.
+const ITERATIONS: usize = 10_000;
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Benchmark
+ *      Helpers
+ */
+
+
+/// Runs the given pattern, reports the amount of cycles it took, and checks that it did not leak.
+fn benchmark(name: &str, pattern: fn() -> ()) -> () {
+    let baseline: usize = heap::used();
+    let start:    u64   = unsafe { _rdtsc() };
+    pattern();
+    let cycles:   u64   = unsafe { _rdtsc() } - start;
+    
+    serial_println!("[{}] {}: {} cycles total, {} cycles per iteration", heap::STRATEGY, name, cycles, cycles / ITERATIONS as u64);
+    assert_eq!(heap::used(), baseline, "The pattern leaked heap memory");
+}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+#[test_case]
+fn test_small_alloc_free() -> () {
+    benchmark("small alloc/free", || {
+        for i in 0..ITERATIONS {
+            let x: Box<u64> = Box::new(i as u64);
+            core::hint::black_box(&x);
+        }
+    });
+}
+
+#[test_case]
+fn test_mixed_sizes() -> () {
+    benchmark("mixed sizes", || {
+        for i in 0..ITERATIONS {
+            let small:  Box<[u8; 16]>   = Box::new([i as u8; 16]);
+            let medium: Box<[u8; 200]>  = Box::new([i as u8; 200]);
+            let large:  Box<[u8; 4096]> = Box::new([i as u8; 4096]);
+            core::hint::black_box((&small, &medium, &large));
+        }
+    });
+}
+
+#[test_case]
+fn test_batch_then_free() -> () {
+    benchmark("batch alloc then free", || {
+        let mut boxes: Vec<Box<[u64; 4]>> = Vec::with_capacity(ITERATIONS);
+        for i in 0..ITERATIONS {
+            boxes.push(Box::new([i as u64; 4]));
+        }
+        
+        // Free every other box first, then the rest, to fragment the heap.
+        let mut i: usize = 0;
+        boxes.retain(|_| { i += 1; i % 2 == 0 });
+        drop(boxes);
+    });
+}
+
+#[test_case]
+fn test_vec_growth() -> () {
+    benchmark("vec growth", || {
+        let mut vec: Vec<u64> = Vec::new();
+        for i in 0..ITERATIONS {
+            vec.push(i as u64);
+        }
+        core::hint::black_box(&vec);
+    });
+}
@@ -0,0 +1,202 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                     /$$                                           /$$       /$$$$$$ /$$$$$$$   /$$$$$$ 
+// | $$  /$$/                    | $$                                          | $$      |_  $$_/| $$__  $$ /$$__  $$
+// | $$ /$$/   /$$$$$$  /$$   /$$| $$$$$$$   /$$$$$$   /$$$$$$   /$$$$$$   /$$$$$$$        | $$  | $$  \ $$| $$  \ $$
+// | $$$$$/   /$$__  $$| $$  | $$| $$__  $$ /$$__  $$ |____  $$ /$$__  $$ /$$__  $$        | $$  | $$$$$$$/| $$  | $$
+// | $$  $$  | $$$$$$$$| $$  | $$| $$  \ $$| $$  \ $$  /$$$$$$$| $$  \__/| $$  | $$        | $$  | $$__  $$| $$  | $$
+// | $$\  $$ | $$_____/| $$  | $$| $$  | $$| $$  | $$ /$$__  $$| $$      | $$  | $$        | $$  | $$  \ $$| $$/$$ $$
+// | $$ \  $$|  $$$$$$$|  $$$$$$$| $$$$$$$/|  $$$$$$/|  $$$$$$$| $$      |  $$$$$$$       /$$$$$$| $$  | $$|  $$$$$$/
+// |__/  \__/ \_______/ \____  $$|_______/  \______/  \_______/|__/       \_______/      |______/|__/  |__/ \____ $$$
+//                      /$$  | $$                                                                                \__/
+//                     |  $$$$$$/                                                                                    
+//                      \______/                                                                                     
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that inject scancodes through the PS/2 controller, so that they arrive on IRQ 1
+//! exactly as a key press would, and are decoded, queued and handed to an awaiting task.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::pin::Pin;
+use core::future::Future;
+use core::panic::PanicInfo;
+use core::sync::atomic::{ AtomicBool, Ordering };
+use core::task::{ Context, Poll, RawWaker, RawWakerVTable, Waker };
+
+use bootloader::{ BootInfo, entry_point };
+use x86_64::instructions::port::Port;
+
+use solas_os::drivers::{ pit, keyboard::{ self, KeyCode, KeyEvent, KeyState, NextKeyEvent } };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The PS/2 controller's data, status and command ports.
+const DATA_PORT:    u16 = 0x60;
+const STATUS_PORT:  u16 = 0x64;
+const COMMAND_PORT: u16 = 0x64;
+
+/// The status bits which are set whilst a byte awaits the kernel, and whilst the controller has yet
+/// to consume the last byte written to it.
+const OUTPUT_FULL: u8 = 0x01;
+const INPUT_FULL:  u8 = 0x02;
+
+/// The controller command which places the next byte written to the data port into the keyboard's
+/// output buffer, as if the keyboard had sent it, raising IRQ 1.
+const WRITE_KEYBOARD_OUTPUT: u8 = 0xD2;
+
+/// The amount of timer ticks to wait for an injected scancode before failing.
+const TIMEOUT_TICKS: u64 = 100;
+
+/// Whether the test waker has been woken.
+static WOKEN: AtomicBool = AtomicBool::new(false);
+
+/// The functions backing the test waker, which records that it was woken.
+static WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(clone_waker, wake, wake, drop_waker);
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Test
+ *      Utilities
+ */
+
+
+/// Writes a byte to one of the controller's ports once it is ready to accept it.
+fn write_controller(port: u16, byte: u8) -> () {
+    let mut status: Port<u8> = Port::new(STATUS_PORT);
+    while unsafe { status.read() } & INPUT_FULL != 0 {
+        core::hint::spin_loop();
+    }
+    unsafe { Port::new(port).write(byte) };
+}
+
+/// Injects each scancode in turn, waiting for the keyboard interrupt to consume it before sending
+/// the next, so that none are overwritten in the controller's single byte buffer.
+fn inject(scancodes: &[u8]) -> () {
+    let mut status: Port<u8> = Port::new(STATUS_PORT);
+    for scancode in scancodes {
+        write_controller(COMMAND_PORT, WRITE_KEYBOARD_OUTPUT);
+        write_controller(DATA_PORT, *scancode);
+
+        let deadline: u64 = pit::ticks() + TIMEOUT_TICKS;
+        while unsafe { status.read() } & OUTPUT_FULL != 0 {
+            assert!(pit::ticks() < deadline, "IRQ 1 never consumed scancode {scancode:#x}");
+            x86_64::instructions::hlt();
+        }
+    }
+}
+
+/// Discards every key event left in the queue, such as those from earlier tests.
+fn drain_events() -> () {
+    while keyboard::try_read_event().is_some() {}
+}
+
+/// Creates another handle to the test waker.
+fn clone_waker(_: *const ()) -> RawWaker {
+    RawWaker::new(core::ptr::null(), &WAKER_VTABLE)
+}
+
+/// Records that the test waker was woken.
+fn wake(_: *const ()) -> () {
+    WOKEN.store(true, Ordering::SeqCst);
+}
+
+/// Releases a handle to the test waker, which owns nothing.
+fn drop_waker(_: *const ()) -> () {}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+#[test_case]
+fn test_scancodes_reach_event_queue() -> () {
+    drain_events();
+    inject(&[0x1E, 0x9E]);
+
+    let event: KeyEvent = keyboard::try_read_event().expect("The key press was not queued");
+    assert_eq!(event.code,      KeyCode::A);
+    assert_eq!(event.state,     KeyState::Pressed);
+    assert_eq!(event.character, Some('a'));
+
+    let event: KeyEvent = keyboard::try_read_event().expect("The key release was not queued");
+    assert_eq!(event.code,  KeyCode::A);
+    assert_eq!(event.state, KeyState::Released);
+    assert_eq!(keyboard::try_read_event(), None);
+}
+
+#[test_case]
+fn test_scancode_wakes_awaiting_task() -> () {
+    drain_events();
+    WOKEN.store(false, Ordering::SeqCst);
+
+    let waker:       Waker        = unsafe { Waker::from_raw(clone_waker(core::ptr::null())) };
+    let mut context: Context      = Context::from_waker(&waker);
+    let mut future:  NextKeyEvent = keyboard::next_event();
+    assert!(Pin::new(&mut future).poll(&mut context).is_pending());
+
+    inject(&[0x30]);
+    assert!(WOKEN.load(Ordering::SeqCst), "The awaiting task was not woken");
+    match Pin::new(&mut future).poll(&mut context) {
+        Poll::Ready(event) => assert_eq!(event.code, KeyCode::B),
+        Poll::Pending      => panic!("The woken future had no key event")
+    }
+
+    inject(&[0xB0]);
+    drain_events();
+}
+
+#[test_case]
+fn test_held_caps_lock_toggles_once() -> () {
+    drain_events();
+
+    // A held key repeats its press, which must not toggle caps lock again.
+    inject(&[0x3A, 0x3A, 0x3A, 0xBA, 0x1E, 0x9E]);
+    let event: KeyEvent = core::iter::from_fn(keyboard::try_read_event).find(|event| event.code == KeyCode::A).unwrap();
+    assert_eq!(event.character, Some('A'));
+    assert!(event.modifiers.caps_lock);
+
+    inject(&[0x3A, 0xBA]);
+    drain_events();
+}
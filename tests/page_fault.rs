@@ -0,0 +1,124 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$                                     /$$$$$$$$                 /$$   /$$    
+// | $$__  $$                                   | $$_____/                | $$  | $$    
+// | $$  \ $$ /$$$$$$   /$$$$$$   /$$$$$$       | $$    /$$$$$$  /$$   /$$| $$ /$$$$$$  
+// | $$$$$$$/|____  $$ /$$__  $$ /$$__  $$      | $$$$$|____  $$| $$  | $$| $$|_  $$_/  
+// | $$____/  /$$$$$$$| $$  \ $$| $$$$$$$$      | $$__/ /$$$$$$$| $$  | $$| $$  | $$    
+// | $$      /$$__  $$| $$  | $$| $$_____/      | $$   /$$__  $$| $$  | $$| $$  | $$ /$$
+// | $$     |  $$$$$$$|  $$$$$$$|  $$$$$$$      | $$  |  $$$$$$$|  $$$$$$/| $$  |  $$$$/
+// |__/      \_______/ \____  $$ \_______/      |__/   \_______/ \______/ |__/   \___/  
+//                     /$$  \ $$                                                        
+//                    |  $$$$$$/                                                        
+//                     \______/                                                         
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that model accesses to unmapped memory, and tests that the OS's page fault
+//! handler reports the faulting address along with the decoded error code.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use core::panic::PanicInfo;
+
+use alloc::{ format, string::String };
+use bootloader::{ BootInfo, entry_point };
+use volatile::Volatile;
+
+use solas_os::{ instructions::exceptions::{ self, PageFaultReport }, serial_print, serial_println, test_terminate, QemuExitCode };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// An address which is guaranteed to not be mapped by the bootloader.
+const UNMAPPED_ADDRESS: u64 = 0xdead_b000;
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    exceptions::set_page_fault_resolver(Some(test_page_fault_resolver));
+    
+    serial_println!("Running 1 test");
+    serial_print!("page_fault::page_fault...\t");
+    test_page_fault();
+
+    loop {}
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+fn test_page_fault() -> () {
+    let unmapped: &mut Volatile<u64> = unsafe { &mut *(UNMAPPED_ADDRESS as *mut Volatile<u64>) };
+    unmapped.write(42);
+
+    panic!("Execution continued after page fault!");
+}
+
+
+/*
+ * Test Page Fault
+ *      Resolver
+ *
+ *  # Note:
+ *  We install a resolver as to allow Qemu to recieve a Success status when the page fault handler
+ *  reports the expected address.
+ */
+
+
+fn test_page_fault_resolver(report: &PageFaultReport) -> bool {
+    assert_eq!(report.address.as_u64(), UNMAPPED_ADDRESS);
+    assert!(report.is_write());
+    assert!(!report.is_present());
+    assert!(!report.is_user());
+
+    // Check the diagnostic that is printed for unresolved faults, as it is written over serial.
+    let output: String = format!("{}", report);
+    assert!(output.starts_with("EXCEPTION: PAGE FAULT"));
+    assert!(output.contains("Address:       0xdeadb000"));
+    assert!(output.contains("Cause:         page not present"));
+    assert!(output.contains("Access:        write"));
+    assert!(output.contains("Privilege:     kernel"));
+
+    serial_println!("[ok]");
+    serial_println!("{}", output);
+    test_terminate(QemuExitCode::Success);
+    loop {}
+}
@@ -0,0 +1,103 @@
+//===================================================================================================================================================================================//
+//
+//  /$$$$$$$           /$$             /$$            /$$$$$$   /$$                                           
+// | $$__  $$         |__/            | $$           /$$__  $$ | $$                                           
+// | $$  \ $$ /$$$$$$  /$$ /$$$$$$$  /$$$$$$        | $$  \__//$$$$$$    /$$$$$$   /$$$$$$   /$$$$$$$ /$$$$$$$
+// | $$$$$$$//$$__  $$| $$| $$__  $$|_  $$_/        |  $$$$$$|_  $$_/   /$$__  $$ /$$__  $$ /$$_____//$$_____/
+// | $$____/| $$  \__/| $$| $$  \ $$  | $$           \____  $$ | $$    | $$  \__/| $$$$$$$$|  $$$$$$|  $$$$$$ 
+// | $$     | $$      | $$| $$  | $$  | $$ /$$       /$$  \ $$ | $$ /$$| $$      | $$_____/ \____  $$\____  $$
+// | $$     | $$      | $$| $$  | $$  |  $$$$/      |  $$$$$$/ |  $$$$/| $$      |  $$$$$$$ /$$$$$$$//$$$$$$$/
+// |__/     |__/      |__/|__/  |__/   \___/         \______/   \___/  |__/       \_______/|_______/|_______/ 
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that print continuously whilst interrupt handlers print as well, and tests that
+//! the OS's print paths never deadlock on their locks.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::panic::PanicInfo;
+
+use bootloader::{ BootInfo, entry_point };
+use core::sync::atomic::{ AtomicUsize, Ordering };
+
+use solas_os::{ println, serial_println_to };
+use solas_os::drivers::{ pit, serial::SerialPortId };
+use solas_os::instructions::interrupts::{ self, InterruptIndex };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The amount of timer interrupts which printed during the test.
+static TIMER_PRINTS: AtomicUsize = AtomicUsize::new(0);
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+/// A timer handler which prints to both the VGA screen and the serial interface on every tick.
+/// # Note
+/// The serial output is sent to COM2 so that it does not interleave with the test results on COM1.
+fn printing_timer_handler(_: InterruptIndex) -> () {
+    let count: usize = TIMER_PRINTS.fetch_add(1, Ordering::Relaxed);
+    println!("timer tick {count}");
+    serial_println_to!(SerialPortId::Com2, "timer tick {}", count);
+}
+
+#[test_case]
+fn test_print_whilst_timer_prints() -> () {
+    interrupts::register_irq(InterruptIndex::Timer, printing_timer_handler).unwrap();
+
+    let start: u64 = pit::ticks();
+    for i in 0..2000 {
+        println!("main loop line {i}");
+        serial_println_to!(SerialPortId::Com2, "main loop line {}", i);
+    }
+    while pit::ticks() < start + 10 {
+        x86_64::instructions::hlt();
+    }
+
+    interrupts::unregister_irq(InterruptIndex::Timer, printing_timer_handler).unwrap();
+    assert!(TIMER_PRINTS.load(Ordering::Relaxed) > 0, "The timer handler never printed");
+}
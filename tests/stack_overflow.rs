@@ -17,7 +17,7 @@
 
 //!
 //! This holds tests that model events which may result in stack overflows and tests againsts the
-//! OS's safeguards..
+//! OS's safeguards, both for the kernel stack and for the interrupt stack a double fault runs on.
 //!
 
 #![no_std]
@@ -28,12 +28,26 @@
 #![reexport_test_harness_main = "test_main"]
 
 use core::panic::PanicInfo;
+use core::sync::atomic::{ AtomicUsize, Ordering };
 
+use bootloader::{ BootInfo, entry_point };
 use volatile::Volatile;
 use lazy_static::lazy_static;
+use x86_64::VirtAddr;
+use x86_64::registers::control::Cr2;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 
-use solas_os::{ instructions::gdt, serial_print, serial_println, test_terminate, QemuExitCode };
+use solas_os::{ instructions::gdt, memory::stack::KernelStack, serial_print, serial_println, test_terminate, QemuExitCode };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The amount of double faults that have been handled so far.
+static DOUBLE_FAULTS: AtomicUsize = AtomicUsize::new(0);
 
 
 /*
@@ -42,13 +56,15 @@ use solas_os::{ instructions::gdt, serial_print, serial_println, test_terminate,
  */
 
 
+entry_point!(test_kernel_main);
+
 /// The entry point for the unit tests library.
-#[no_mangle]
-pub extern "C" fn _start() -> ! {
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::memory::init(boot_info);
     gdt::init();
     init_test_idt();
     
-    serial_println!("Running 1 test");
+    serial_println!("Running 2 tests");
     serial_print!("stack_overflow::stack_overflow...\t");
     test_stack_overflow();
 
@@ -69,19 +85,28 @@ fn panic(info: &PanicInfo) -> ! {
  */
 
 
+// trigger a stack overflow
+#[allow(unconditional_recursion)]
+fn stack_overflow() {
+    stack_overflow();         // For each recursion, the return address is pushed.
+    Volatile::new(0).read();  // Prevent tail recursion optimizations.
+}
+
 fn test_stack_overflow() {
-    
-    // trigger a stack overflow
-    #[allow(unconditional_recursion)]
-    fn stack_overflow() {
-        stack_overflow();         // For each recursion, the return address is pushed.
-        Volatile::new(0).read();  // Prevent tail recursion optimizations.
-    }
     stack_overflow();
 
     panic!("Execution continued after stack overflow!");
 }
 
+/// Overflows the double fault stack from within the double fault handler. Writing into the guard
+/// page below it faults, and as that fault cannot be delivered on the overflowed stack, a second
+/// double fault is raised which starts over at the top of the double fault stack.
+fn test_ist_stack_overflow() -> ! {
+    stack_overflow();
+
+    panic!("Execution continued after the double fault stack overflowed!");
+}
+
 
 /*
  * Test IDT
@@ -110,7 +135,24 @@ fn init_test_idt() -> () {
 }
 
 extern "x86-interrupt" fn test_double_fault_handler(_: InterruptStackFrame, _: u64) -> ! {
-    serial_println!("[ok]");
-    test_terminate(QemuExitCode::Success);
-    loop {}
+    match DOUBLE_FAULTS.fetch_add(1, Ordering::SeqCst) {
+
+        // The kernel stack overflowed, so continue by overflowing this handler's own stack.
+        0 => {
+            serial_println!("[ok]");
+            serial_print!("stack_overflow::ist_stack_overflow...\t");
+            test_ist_stack_overflow();
+        },
+
+        // The double fault stack overflowed, which must have been caught by its guard page.
+        _ => {
+            let stack:   &KernelStack = gdt::interrupt_stack(gdt::DOUBLE_FAULT_IST_INDEX).expect("No double fault stack is set up");
+            let address: VirtAddr     = Cr2::read();
+            assert!(stack.is_guard_hit(address), "The fault at {:?} did not hit the double fault stack's guard page", address);
+            
+            serial_println!("[ok]");
+            test_terminate(QemuExitCode::Success);
+            loop {}
+        }
+    }
 }
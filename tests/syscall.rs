@@ -0,0 +1,194 @@
+//===================================================================================================================================================================================//
+//
+//   /$$$$$$                                          /$$ /$$
+//  /$$__  $$                                        | $$| $$
+// | $$  \__/ /$$   /$$  /$$$$$$$  /$$$$$$$  /$$$$$$ | $$| $$
+// |  $$$$$$ | $$  | $$ /$$_____/ /$$_____/ |____  $$| $$| $$
+//  \____  $$| $$  | $$|  $$$$$$ | $$        /$$$$$$$| $$| $$
+//  /$$  \ $$| $$  | $$ \____  $$| $$       /$$__  $$| $$| $$
+// |  $$$$$$/|  $$$$$$$ /$$$$$$$/|  $$$$$$$|  $$$$$$$| $$| $$
+//  \______/  \____  $$|_______/  \_______/ \_______/|__/|__/
+//            /$$  | $$                                      
+//           |  $$$$$$/                                      
+//            \______/                                       
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that make system calls from a user mode stub, and tests that each call returns
+//! the expected result or error.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks, asm_const)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::arch::global_asm;
+use core::panic::PanicInfo;
+
+use bootloader::{ BootInfo, entry_point };
+use x86_64::VirtAddr;
+use x86_64::structures::paging::{ Page, PageTableFlags, PhysFrame };
+
+use solas_os::instructions::{ user_mode, syscall::{ self, SyscallError } };
+use solas_os::memory::{ frame_allocator, paging };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The addresses at which the user mode code and stack are mapped, within a level 4 entry that the
+/// kernel leaves untouched.
+const USER_CODE:  u64 = 0x2000_0000_0000;
+const USER_STACK: u64 = 0x2000_0010_0000;
+
+/// The last page of the lower canonical half, whose final instruction returns to a non-canonical
+/// address.
+const CANONICAL_END_PAGE: u64 = 0x7fff_ffff_f000;
+
+/// The exit code the user mode stub exits with.
+const EXIT_CODE: u64 = 0x5a;
+
+/// The message the user mode stub writes.
+const MESSAGE: &str = "Hello from ring 3!\n";
+
+
+/*
+ * User Mode
+ *      Stub
+ */
+
+
+// A position independent stub which is copied into a user accessible page. It writes a message,
+// asks for its process identifier, makes an unknown call and a write from a kernel address, pushes
+// each result onto its stack for the test to inspect, and then exits.
+global_asm!(
+    ".global syscall_user_stub",
+    ".global syscall_user_stub_end",
+    "syscall_user_stub:",
+    "    mov eax, {write}",
+    "    mov edi, {stdout}",
+    "    lea rsi, [rip + syscall_user_message]",
+    "    mov edx, {message_length}",
+    "    syscall",
+    "    push rax",
+    "    mov eax, {getpid}",
+    "    syscall",
+    "    push rax",
+    "    mov eax, 0xffff",
+    "    syscall",
+    "    push rax",
+    "    mov eax, {write}",
+    "    mov edi, {stdout}",
+    "    mov rsi, 0x200000",
+    "    mov edx, 1",
+    "    syscall",
+    "    push rax",
+    "    mov eax, {exit}",
+    "    mov edi, {exit_code}",
+    "    syscall",
+    "    ud2",
+    "syscall_user_message:",
+    "    .ascii \"Hello from ring 3!\\n\"",
+    "syscall_user_stub_end:",
+    write          = const syscall::SYS_WRITE,
+    getpid         = const syscall::SYS_GETPID,
+    exit           = const syscall::SYS_EXIT,
+    stdout         = const syscall::STDOUT,
+    message_length = const MESSAGE.len(),
+    exit_code      = const EXIT_CODE
+);
+
+extern "C" {
+    static syscall_user_stub:     u8;
+    static syscall_user_stub_end: u8;
+}
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+#[test_case]
+fn test_syscalls_from_user_mode() -> () {
+    let code:        Page      = Page::containing_address(VirtAddr::new(USER_CODE));
+    let stack:       Page      = Page::containing_address(VirtAddr::new(USER_STACK));
+    let code_frame:  PhysFrame = paging::map_new(code, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE).unwrap();
+    let stack_frame: PhysFrame = paging::map_new(stack, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE | PageTableFlags::NO_EXECUTE).unwrap();
+    unsafe {
+        let start:  *const u8 = &syscall_user_stub;
+        let length: usize     = (&syscall_user_stub_end as *const u8).offset_from(start) as usize;
+        core::ptr::copy_nonoverlapping(start, code.start_address().as_mut_ptr(), length);
+    }
+
+    let stack_top: VirtAddr = stack.start_address() + 4096u64;
+    assert_eq!(unsafe { user_mode::enter(code.start_address(), stack_top) }, EXIT_CODE);
+
+    // The stub pushed the result of each call in order.
+    let results: *const u64 = stack_top.as_ptr();
+    unsafe {
+        assert_eq!(SyscallError::decode(results.sub(1).read()), Ok(MESSAGE.len() as u64));
+        assert_eq!(SyscallError::decode(results.sub(2).read()), Ok(syscall::INIT_PID));
+        assert_eq!(SyscallError::decode(results.sub(3).read()), Err(SyscallError::NoSuchCall));
+        assert_eq!(SyscallError::decode(results.sub(4).read()), Err(SyscallError::BadAddress));
+    }
+
+    for (page, frame) in [(code, code_frame), (stack, stack_frame)] {
+        assert_eq!(paging::unmap(page), Ok(frame));
+        unsafe { frame_allocator::deallocate_frame(frame) };
+    }
+}
+
+#[test_case]
+fn test_syscall_at_canonical_boundary() -> () {
+    let code:        Page      = Page::containing_address(VirtAddr::new(CANONICAL_END_PAGE));
+    let stack:       Page      = Page::containing_address(VirtAddr::new(USER_STACK));
+    let code_frame:  PhysFrame = paging::map_new(code, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE).unwrap();
+    let stack_frame: PhysFrame = paging::map_new(stack, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE | PageTableFlags::NO_EXECUTE).unwrap();
+
+    // A `syscall` in the last two bytes of the page returns to the first non-canonical address.
+    let entry: VirtAddr = code.start_address() + 4094u64;
+    unsafe { entry.as_mut_ptr::<[u8; 2]>().write([0x0f, 0x05]) };
+
+    let result: u64 = unsafe { user_mode::enter(entry, stack.start_address() + 4096u64) };
+    assert_eq!(SyscallError::decode(result), Err(SyscallError::BadAddress));
+
+    for (page, frame) in [(code, code_frame), (stack, stack_frame)] {
+        assert_eq!(paging::unmap(page), Ok(frame));
+        unsafe { frame_allocator::deallocate_frame(frame) };
+    }
+}
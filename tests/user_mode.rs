@@ -0,0 +1,169 @@
+//===================================================================================================================================================================================//
+//
+//  /$$   /$$                                     /$$      /$$                 /$$          
+// | $$  | $$                                    | $$$    /$$$                | $$          
+// | $$  | $$  /$$$$$$$  /$$$$$$   /$$$$$$       | $$$$  /$$$$  /$$$$$$   /$$$$$$$  /$$$$$$ 
+// | $$  | $$ /$$_____/ /$$__  $$ /$$__  $$      | $$ $$/$$ $$ /$$__  $$ /$$__  $$ /$$__  $$
+// | $$  | $$|  $$$$$$ | $$$$$$$$| $$  \__/      | $$  $$$| $$| $$  \ $$| $$  | $$| $$$$$$$$
+// | $$  | $$ \____  $$| $$_____/| $$            | $$\  $ | $$| $$  | $$| $$  | $$| $$_____/
+// |  $$$$$$/ /$$$$$$$/|  $$$$$$$| $$            | $$ \/  | $$|  $$$$$$/|  $$$$$$$|  $$$$$$$
+//  \______/ |_______/  \_______/|__/            |__/     |__/ \______/  \_______/ \_______/
+//
+//===================================================================================================================================================================================//
+
+//?
+//? Created by LunaticWyrm467
+//?
+
+//!
+//! This holds tests that drop into user mode, and tests that exceptions raised there bring control
+//! back into the kernel.
+//!
+
+#![no_std]
+#![no_main]
+
+#![feature(custom_test_frameworks)]
+#![test_runner(solas_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::panic::PanicInfo;
+use core::sync::atomic::{ AtomicU64, Ordering };
+
+use bootloader::{ BootInfo, entry_point };
+use x86_64::VirtAddr;
+use x86_64::instructions::segmentation::{ CS, Segment };
+use x86_64::registers::control::{ Cr0, Cr0Flags };
+use x86_64::structures::paging::{ Page, PageTableFlags, PhysFrame };
+
+use solas_os::instructions::{ gdt, user_mode, exceptions::{ self, Exception, ExceptionAction, ExceptionPolicy, ExceptionReport } };
+use solas_os::memory::{ frame_allocator, paging };
+
+
+/*
+ * Constant & Static
+ *      Declarations
+ */
+
+
+/// The addresses at which the user mode code and stack are mapped, within a level 4 entry that the
+/// kernel leaves untouched.
+const USER_CODE:  u64 = 0x2000_0000_0000;
+const USER_STACK: u64 = 0x2000_0010_0000;
+
+/// A user mode stub which raises two breakpoints, and then spins forever.
+const USER_STUB: [u8; 4] = [
+    0xcc,          // int3
+    0xcc,          // int3
+    0xeb, 0xfe     // jmp $
+];
+
+/// A user mode stub which sets the alignment check flag, and then reads from a misaligned address
+/// on its stack.
+const ALIGNMENT_STUB: [u8; 16] = [
+    0x9c,                                              // pushfq
+    0x48, 0x81, 0x0c, 0x24, 0x00, 0x00, 0x04, 0x00,    // or qword ptr [rsp], 0x40000
+    0x9d,                                              // popfq
+    0x8b, 0x44, 0x24, 0xf9,                            // mov eax, [rsp - 7]
+    0xeb, 0xfe                                         // jmp $
+];
+
+/// The amount of breakpoints which were raised from user mode.
+static USER_BREAKPOINTS: AtomicU64 = AtomicU64::new(0);
+
+
+/*
+ * Unit Tests
+ *      Entry Point
+ */
+
+
+entry_point!(test_kernel_main);
+
+/// The entry point for the unit tests library.
+fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
+    solas_os::init(boot_info);
+    test_main();
+    solas_os::hlt_loop();
+}
+
+/// The tests panic handler.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    solas_os::test_panic_handler(info)
+}
+
+
+/*
+ * Test
+ *      Utilities
+ */
+
+
+/// Copies a stub into a freshly mapped user mode page and enters it with its own stack, routing its
+/// exceptions through the given policy, and returns the value it returned to the kernel with.
+fn run_user_stub(stub: &[u8], policy: ExceptionPolicy) -> u64 {
+    let code:        Page      = Page::containing_address(VirtAddr::new(USER_CODE));
+    let stack:       Page      = Page::containing_address(VirtAddr::new(USER_STACK));
+    let code_frame:  PhysFrame = paging::map_new(code, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE).unwrap();
+    let stack_frame: PhysFrame = paging::map_new(stack, PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE | PageTableFlags::NO_EXECUTE).unwrap();
+    unsafe { core::ptr::copy_nonoverlapping(stub.as_ptr(), code.start_address().as_mut_ptr(), stub.len()) };
+
+    exceptions::set_exception_policy(Some(policy));
+    let returned: u64 = unsafe { user_mode::enter(code.start_address(), stack.start_address() + 4096u64) };
+    exceptions::set_exception_policy(None);
+
+    for (page, frame) in [(code, code_frame), (stack, stack_frame)] {
+        assert_eq!(paging::unmap(page), Ok(frame));
+        unsafe { frame_allocator::deallocate_frame(frame) };
+    }
+    returned
+}
+
+
+/*
+ * Unit Test
+ *      Cases
+ */
+
+
+/// Resumes the first breakpoint raised from user mode, and returns to the kernel on the second.
+fn user_breakpoint_policy(report: &ExceptionReport) -> ExceptionAction {
+    if report.exception != Exception::Breakpoint || !report.from_user_mode() {
+        return ExceptionAction::Panic;
+    }
+    
+    match USER_BREAKPOINTS.fetch_add(1, Ordering::SeqCst) + 1 {
+        1     => ExceptionAction::Resume,
+        count => unsafe { user_mode::return_to_kernel(count) }
+    }
+}
+
+#[test_case]
+fn test_enter_user_mode_and_return() -> () {
+    assert_eq!(run_user_stub(&USER_STUB, user_breakpoint_policy), 2);
+    assert_eq!(CS::get_reg(), gdt::selectors().code_selector);
+    assert!(x86_64::instructions::interrupts::are_enabled());
+}
+
+/// Checks the alignment check raised from user mode, and returns to the kernel with its vector.
+fn user_alignment_check_policy(report: &ExceptionReport) -> ExceptionAction {
+    if report.exception != Exception::AlignmentCheck || !report.from_user_mode() {
+        return ExceptionAction::Panic;
+    }
+
+    assert_eq!(report.error_code, Some(0));
+    unsafe { user_mode::return_to_kernel(report.exception.vector() as u64) }
+}
+
+#[test_case]
+fn test_alignment_check_from_user_mode() -> () {
+
+    // Misaligned accesses are only checked in user mode, and only whilst CR0.AM and RFLAGS.AC are
+    // both set, the latter of which the stub sets itself.
+    unsafe { Cr0::update(|flags| flags.insert(Cr0Flags::ALIGNMENT_MASK)) };
+    let returned: u64 = run_user_stub(&ALIGNMENT_STUB, user_alignment_check_policy);
+    unsafe { Cr0::update(|flags| flags.remove(Cr0Flags::ALIGNMENT_MASK)) };
+
+    assert_eq!(returned, Exception::AlignmentCheck.vector() as u64);
+}